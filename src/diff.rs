@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem;
+
+use crate::mem::*;
+use crate::pmdm::*;
+use crate::symbol::*;
+
+// Run of bytes that differ between two memory images, None where a byte is uninitialized
+pub struct Difference {
+    pub address: u64,
+    pub old: Vec<Option<u8>>,
+    pub new: Vec<Option<u8>>,
+    // Field of PMDM the bytes belong to, if any
+    pub symbol: Option<Symbol>,
+}
+
+// Array and index of a byte array element, like name.buffer[5]
+fn byte_array_element(symbol: &Symbol) -> Option<(&str, usize)> {
+    if symbol.size != 1 { return None; }
+    let (array, index) = symbol.path.strip_suffix(']')?.rsplit_once('[')?;
+    Some((array, index.parse().ok()?))
+}
+
+fn format_bytes(bytes: &[Option<u8>]) -> String {
+    bytes.iter().map(|byte| match byte {
+        Some(byte) => format!("{:02x}", byte),
+        None => "??".to_string(),
+    }).collect()
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x{:x} ", self.address)?;
+        match &self.symbol {
+            Some(symbol) => match byte_array_element(symbol) {
+                // Byte arrays show the range of elements that changed
+                Some((array, first)) if self.old.len() > 1 => {
+                    write!(f, "{}[{}..{}]", array, first, first + self.old.len())?
+                },
+                _ => write!(f, "{}", symbol.path)?,
+            },
+            None => write!(f, "(outside PMDM)")?,
+        }
+        write!(f, ": {} -> {}", format_bytes(&self.old), format_bytes(&self.new))
+    }
+}
+
+// Compare two images byte by byte, grouping changed bytes by the PMDM field they belong to.
+// Neighbouring bytes of a byte array, like a string buffer, are grouped together.
+pub fn diff_memory(
+    old: &Memory, new: &Memory, this: Pointer<PauseMenuDataMgr>
+) -> Vec<Difference> {
+    let mut changed = BTreeMap::new();
+    for (image, other) in [(old, new), (new, old)] {
+        for (start, bytes) in image.regions() {
+            for (address, &byte) in (start..).zip(bytes) {
+                if other.byte(address) != Some(byte) {
+                    changed.insert(address, (old.byte(address), new.byte(address)));
+                }
+            }
+        }
+    }
+
+    let mut differences: Vec<Difference> = vec![];
+    for (address, (old_byte, new_byte)) in changed {
+        let symbol = symbolize(this, address);
+
+        // Values are shown whole, so multi-byte numbers and pointers read as a unit. Bigger
+        // symbols are unions or padding in objects, which only show the bytes that changed.
+        if let Some(symbol) = &symbol && (2..=mem::size_of::<u64>()).contains(&symbol.size) {
+            if differences.last().is_some_and(|last| last.symbol.as_ref() == Some(symbol)) {
+                continue;
+            }
+            let range = symbol.address..symbol.address + symbol.size as u64;
+            differences.push(Difference {
+                address: symbol.address,
+                old: range.clone().map(|address| old.byte(address)).collect(),
+                new: range.map(|address| new.byte(address)).collect(),
+                symbol: Some(symbol.clone()),
+            });
+            continue;
+        }
+
+        if let Some(last) = differences.last_mut()
+            && last.address + last.old.len() as u64 == address
+            && same_field(last.symbol.as_ref(), symbol.as_ref()) {
+            last.old.push(old_byte);
+            last.new.push(new_byte);
+            continue;
+        }
+        differences.push(Difference { address, old: vec![old_byte], new: vec![new_byte], symbol });
+    }
+    differences
+}
+
+// Whether neighbouring bytes belong in one difference
+fn same_field(last: Option<&Symbol>, next: Option<&Symbol>) -> bool {
+    match (last, next) {
+        (None, None) => true,
+        (Some(last), Some(next)) => last == next || matches!(
+            (byte_array_element(last), byte_array_element(next)),
+            (Some((last, _)), Some((next, _))) if last == next
+        ),
+        _ => false,
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+use std::io;
+use std::ops::Range;
+
+use crate::platform::*;
+use crate::pmdm::*;
+use crate::version::*;
+
+#[derive(Debug)]
+pub enum SimError {
+    // Memory errors carry the field path of the address, where it is known
+    OutOfAslrRange { range: Range<u64>, symbol: Option<String> },
+    UninitializedRead { range: Range<u64>, symbol: Option<String> },
+    DerefDenied { address: u64, symbol: Option<String> },
+    Misaligned { address: u64, symbol: Option<String> },
+    WatchpointHit { action: String, address: u64, symbol: Option<String> },
+    BadWatchpoint { spec: String },
+    BadHeapBase { text: String },
+    DumpSizeMismatch { version: &'static str, expected: usize, found: usize },
+    UnknownDumpSize { found: usize },
+    UnknownGameVersion { name: String },
+    AmbiguousGameVersion { candidates: Vec<&'static str> },
+    WrongPlatform { version: &'static str, platform: Platform },
+    BadDumpFile { message: String },
+    BadTranslationFile(serde_json::Error),
+    BadInventoryFile(serde_json::Error),
+    BadScript { line: usize, message: String },
+    AssertionFailed { line: usize, message: String },
+    Io(io::Error),
+    GameFreeze { list: ItemList, cycle_nodes: Vec<CycleNode> },
+}
+
+fn describe_symbol(symbol: &Option<String>) -> String {
+    symbol.as_ref().map(|symbol| format!(" ({})", symbol)).unwrap_or_default()
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OutOfAslrRange { range, symbol } => write!(
+                f, "Address range 0x{:x}-0x{:x}{} is outside ASLR range",
+                range.start, range.end, describe_symbol(symbol)
+            ),
+            Self::UninitializedRead { range, symbol } => write!(
+                f, "Uninitialized memory in range 0x{:x}-0x{:x}{}",
+                range.start, range.end, describe_symbol(symbol)
+            ),
+            Self::DerefDenied { address, symbol } => write!(
+                f, "Invalid address 0x{:x} dereferenced{}", address,
+                symbol.as_ref().map(|symbol| format!(" from {}", symbol)).unwrap_or_default()
+            ),
+            Self::Misaligned { address, symbol } => write!(
+                f, "Misaligned access at 0x{:x}{}", address, describe_symbol(symbol)
+            ),
+            Self::WatchpointHit { action, address, symbol } => write!(
+                f, "Watchpoint hit by {} writing 0x{:x}{}", action, address, describe_symbol(symbol)
+            ),
+            Self::BadWatchpoint { spec } => write!(
+                f, "Invalid watchpoint '{}', expected an address, an address range like \
+                    0x1000-0x1008 or a PMDM field path", spec
+            ),
+            Self::BadHeapBase { text } => write!(
+                f, "Invalid heap base '{}', expected an address like 0x{:x}", text,
+                SYNTHETIC_HEAP_BASE
+            ),
+            Self::DumpSizeMismatch { version, expected, found } => write!(
+                f, "PMDM dump size does not match {} (expected 0x{:x}, found 0x{:x})",
+                version, expected, found
+            ),
+            Self::UnknownDumpSize { found } => {
+                write!(f, "PMDM dump size 0x{:x} does not match any known version:", found)?;
+                for version in &GAME_VERSIONS {
+                    write!(f, " {} (0x{:x})", version.name, version.pmdm_size)?;
+                }
+                Ok(())
+            },
+            Self::UnknownGameVersion { name } => {
+                write!(f, "Unknown game version '{}', expected one of:", name)?;
+                for version in &GAME_VERSIONS { write!(f, " '{}'", version.name)?; }
+                Ok(())
+            },
+            Self::AmbiguousGameVersion { candidates } => {
+                write!(f, "Cannot tell the game version of the PMDM dump, pass --game-version with \
+                    one of:")?;
+                for name in candidates { write!(f, " '{}'", name)?; }
+                Ok(())
+            },
+            Self::WrongPlatform { version, platform } => write!(
+                f, "{} needs a {} build of the simulator from `{}` (this one is for {})",
+                version, platform, build_command(*platform), PLATFORM
+            ),
+            Self::BadDumpFile { message } => write!(f, "Invalid dump file: {}", message),
+            Self::BadTranslationFile(err) => write!(f, "Invalid translation file: {}", err),
+            Self::BadInventoryFile(err) => write!(f, "Invalid inventory file: {}", err),
+            Self::BadScript { line, message } => write!(
+                f, "Invalid script on line {}: {}", line, message
+            ),
+            Self::AssertionFailed { line, message } => write!(
+                f, "Assertion on line {} failed: {}", line, message
+            ),
+            Self::Io(err) => write!(f, "{}", err),
+            Self::GameFreeze { list, cycle_nodes } => {
+                write!(f, "Game has frozen due to cyclic nodes in {:?}:", list)?;
+                for node in cycle_nodes {
+                    write!(f, " 0x{:x} ({})", node.address, node.name.as_deref().unwrap_or("?"))?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
+
+impl From<io::Error> for SimError {
+    fn from(err: io::Error) -> Self { Self::Io(err) }
+}
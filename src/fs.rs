@@ -1,33 +1,463 @@
 use std::fs::File;
-use std::io;
-use std::io::Read;
+use std::io::{Read, Write};
+use std::mem;
+use std::ptr;
+use std::slice;
+use std::str;
 
+use serde::Deserialize;
 use serde_json;
 
-use crate::PauseMenuDataMgr;
+use crate::error::*;
+use crate::mem::*;
+use crate::platform::*;
+use crate::script::*;
+use crate::types::*;
+use crate::version::*;
 
-pub fn read_dump(path: &str) -> io::Result<(u64, Vec<u8>)> {
+#[derive(Deserialize)]
+struct InventoryModifier {
+    flags: u32,
+    value: u32,
+}
+
+#[derive(Deserialize)]
+struct InventoryCookData {
+    health_recover: i32,
+    effect_duration: i32,
+    sell_price: i32,
+    effect_id: i32,
+    effect_level: f32,
+}
+
+#[derive(Deserialize)]
+struct InventoryItem {
+    name: String,
+    #[serde(rename = "type")]
+    item_type: PouchItemType,
+    value: i32,
+    #[serde(default)]
+    equipped: bool,
+    modifier: Option<InventoryModifier>,
+    cook: Option<InventoryCookData>,
+}
+
+impl From<InventoryItem> for GameDataItem {
+    fn from(item: InventoryItem) -> Self {
+        let data = if let Some(modifier) = item.modifier {
+            let mut weapon = WeaponData::default();
+            weapon.modifier_value.set(modifier.value);
+            weapon.modifier.set(modifier.flags);
+            Data { weapon }
+        } else if let Some(cook) = item.cook { Data { cook: CookData {
+            health_recover: Guest::new(cook.health_recover),
+            effect_duration: Guest::new(cook.effect_duration),
+            sell_price: Guest::new(cook.sell_price),
+            effect_id: Guest::new(cook.effect_id as f32),
+            effect_level: Guest::new(cook.effect_level),
+        } } } else { Data::default() };
+
+        Self {
+            name: item.name,
+            item_type: item.item_type,
+            equipped: item.equipped,
+            value: item.value,
+            data,
+        }
+    }
+}
+
+// Multi-region dumps start with DUMP_MAGIC, followed by little-endian fields:
+//   u32 format version
+//   u16 game version tag length, tag bytes (a GameVersion name)
+//   u64 PMDM address
+//   u32 region count, then for each region u64 address, u64 length, data bytes
+//   since format version 2, the script state:
+//     u8 paused, u8 whether there is a save file
+//     u32 saved item count, then for each item u16 name length, name bytes, i32 item type,
+//     u8 equipped, i32 value, Data bytes as the guest lays them out
+// Anything else is read as a legacy dump, a u64 PMDM address followed by the PMDM bytes.
+pub const DUMP_MAGIC: &[u8; 8] = b"PMDMDUMP";
+pub const DUMP_FORMAT_VERSION: u32 = 2;
+
+// Guest memory captured from the console, along with where PMDM lies in it. Snapshots also
+// keep the script state, so a run can pick up where it stopped.
+pub struct Dump {
+    pub version: &'static GameVersion,
+    pub pmdm_address: u64,
+    pub regions: Vec<(u64, Vec<u8>)>,
+    pub state: ScriptState,
+}
+
+// Cursor over the fields of a multi-region dump
+struct DumpReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DumpReader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], SimError> {
+        let (bytes, rest) = self.data.split_at_checked(len).ok_or(
+            SimError::BadDumpFile { message: "unexpected end of file".to_string() }
+        )?;
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, SimError> { Ok(self.bytes(0x1)?[0]) }
+
+    fn u16(&mut self) -> Result<u16, SimError> {
+        Ok(u16::from_le_bytes(self.bytes(0x2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, SimError> {
+        Ok(u32::from_le_bytes(self.bytes(0x4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, SimError> {
+        Ok(u64::from_le_bytes(self.bytes(0x8)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, SimError> { Ok(self.u32()? as i32) }
+
+    fn string(&mut self, what: &str) -> Result<&'a str, SimError> {
+        let len = self.u16()? as usize;
+        str::from_utf8(self.bytes(len)?).map_err(
+            |_| SimError::BadDumpFile { message: format!("{} is not UTF-8", what) }
+        )
+    }
+
+    fn state(&mut self) -> Result<ScriptState, SimError> {
+        let paused = self.u8()? != 0;
+        let has_save_file = self.u8()? != 0;
+        let item_count = self.u32()?;
+        let mut save_file = vec![];
+        for _ in 0..item_count {
+            let name = self.string("saved item name")?.to_string();
+            let item_type = PouchItemType::from_raw(self.i32()?);
+            let equipped = self.u8()? != 0;
+            let value = self.i32()?;
+            let data = self.bytes(mem::size_of::<Data>())?;
+            let data = unsafe { ptr::read_unaligned(data.as_ptr().cast::<Data>()) };
+            save_file.push(GameDataItem { name, item_type, equipped, value, data });
+        }
+        Ok(ScriptState { paused, save_file: has_save_file.then_some(save_file) })
+    }
+}
+
+// Read dump, checking PMDM against the given game version or the one the dump names or
+// looks like
+pub fn read_dump(path: &str, version: Option<&str>) -> Result<Dump, SimError> {
     // Read dump
     let mut file = File::open(path)?;
     let mut buffer = vec![];
     file.read_to_end(&mut buffer)?;
 
+    let version = version.map(GameVersion::find).transpose()?;
+    match buffer.strip_prefix(DUMP_MAGIC) {
+        Some(data) => read_region_dump(data, version),
+        None => read_legacy_dump(buffer, version),
+    }
+}
+
+// Write dump in the multi-region format
+pub fn write_dump(path: &str, dump: &Dump) -> Result<(), SimError> {
+    let mut buffer = DUMP_MAGIC.to_vec();
+    buffer.extend_from_slice(&DUMP_FORMAT_VERSION.to_le_bytes());
+    buffer.extend_from_slice(&(dump.version.name.len() as u16).to_le_bytes());
+    buffer.extend_from_slice(dump.version.name.as_bytes());
+    buffer.extend_from_slice(&dump.pmdm_address.to_le_bytes());
+    buffer.extend_from_slice(&(dump.regions.len() as u32).to_le_bytes());
+    for (address, data) in &dump.regions {
+        buffer.extend_from_slice(&address.to_le_bytes());
+        buffer.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        buffer.extend_from_slice(data);
+    }
+
+    let save_file = dump.state.save_file.as_deref();
+    buffer.push(dump.state.paused as u8);
+    buffer.push(save_file.is_some() as u8);
+    buffer.extend_from_slice(&(save_file.unwrap_or_default().len() as u32).to_le_bytes());
+    for item in save_file.unwrap_or_default() {
+        buffer.extend_from_slice(&(item.name.len() as u16).to_le_bytes());
+        buffer.extend_from_slice(item.name.as_bytes());
+        buffer.extend_from_slice(&item.item_type.raw().to_le_bytes());
+        buffer.push(item.equipped as u8);
+        buffer.extend_from_slice(&item.value.to_le_bytes());
+        buffer.extend_from_slice(unsafe { slice::from_raw_parts(
+            ptr::from_ref(&item.data).cast::<u8>(), mem::size_of::<Data>()
+        ) });
+    }
+
+    File::create(path)?.write_all(&buffer)?;
+    Ok(())
+}
+
+// Check that a region lies in the range guest pointers may point into, so it can be loaded
+fn check_region(address: u64, len: usize) -> Result<(), SimError> {
+    match address.checked_add(len as u64) {
+        Some(end) if address >= ASLR_START && end <= ASLR_END => Ok(()),
+        _ => Err(SimError::BadDumpFile {
+            message: format!("region of 0x{:x} bytes at 0x{:x} is out of range", len, address)
+        }),
+    }
+}
+
+fn read_legacy_dump(
+    buffer: Vec<u8>, version: Option<&'static GameVersion>
+) -> Result<Dump, SimError> {
+    if buffer.len() < 0x8 { return Err(SimError::UnknownDumpSize { found: buffer.len() }); }
+
     // Seperate address and data
     let address = u64::from_le_bytes(buffer[..0x8].try_into().unwrap());
     let data = buffer[0x8..].to_vec();
 
-    // Check dump size
-    assert_eq!(
-        data.len(), std::mem::size_of::<PauseMenuDataMgr>(),
-        "PMDM dump size does not match (expected 0x{:x}, found 0x{:x})",
-        std::mem::size_of::<PauseMenuDataMgr>() + 8, buffer.len()
-    );
+    let version = match version {
+        Some(version) => version,
+        None => GameVersion::detect(&data)?,
+    };
+    version.check_dump(&data)?;
+    check_region(address, data.len())?;
 
-    Ok((address, data))
+    let regions = vec![(address, data)];
+    Ok(Dump { version, pmdm_address: address, regions, state: ScriptState::default() })
 }
 
-pub fn read_translations(path: &str) -> io::Result<serde_json::Value> {
+fn read_region_dump(
+    data: &[u8], version: Option<&'static GameVersion>
+) -> Result<Dump, SimError> {
+    let mut reader = DumpReader { data };
+    let format_version = reader.u32()?;
+    if !(1..=DUMP_FORMAT_VERSION).contains(&format_version) {
+        return Err(SimError::BadDumpFile {
+            message: format!("unsupported format version {}", format_version)
+        });
+    }
+
+    // A version given on the command line overrides the tag
+    let tag = reader.string("game version tag")?;
+    let version = match version {
+        Some(version) => version,
+        None => GameVersion::find(tag)?,
+    };
+
+    let pmdm_address = reader.u64()?;
+    let region_count = reader.u32()?;
+    let mut regions = vec![];
+    for _ in 0..region_count {
+        let address = reader.u64()?;
+        let len = reader.u64()? as usize;
+        check_region(address, len)?;
+        regions.push((address, reader.bytes(len)?.to_vec()));
+    }
+    let state = if format_version >= 2 { reader.state()? } else { ScriptState::default() };
+    if !reader.data.is_empty() {
+        return Err(SimError::BadDumpFile { message: "trailing data after dump".to_string() });
+    }
+
+    // Check the part of the region holding PMDM
+    let (start, data) = regions.iter().find(
+        |(start, data)| (*start..*start + data.len() as u64).contains(&pmdm_address)
+    ).ok_or(SimError::BadDumpFile {
+        message: format!("no region contains PMDM at 0x{:x}", pmdm_address)
+    })?;
+    let offset = (pmdm_address - start) as usize;
+    version.check_dump(&data[offset..data.len().min(offset + version.pmdm_size)])?;
+
+    Ok(Dump { version, pmdm_address, regions, state })
+}
+
+// Write traced writes, one per line
+pub fn write_trace(path: &str, tracer: &Tracer) -> Result<(), SimError> {
+    let mut file = File::create(path)?;
+    for write in &tracer.writes { writeln!(file, "{}", write)?; }
+    Ok(())
+}
+
+// Read list of items in save order
+pub fn read_inventory(path: &str) -> Result<GameData, SimError> {
     let file = File::open(path)?;
-    let translations = serde_json::from_reader(file).unwrap();
+    let items: Vec<InventoryItem> = serde_json::from_reader(file).map_err(
+        SimError::BadInventoryFile
+    )?;
+    Ok(items.into_iter().map(GameDataItem::from).collect())
+}
+
+pub fn read_translations(path: &str) -> Result<serde_json::Value, SimError> {
+    let file = File::open(path)?;
+    let translations = serde_json::from_reader(file).map_err(SimError::BadTranslationFile)?;
     Ok(translations)
 }
+
+pub fn read_script(path: &str) -> Result<Vec<Step>, SimError> {
+    let mut source = String::new();
+    File::open(path)?.read_to_string(&mut source)?;
+    parse_script(&source)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use super::*;
+
+    // File in the temp directory, unique to this test run
+    fn temp_path(name: &str) -> String {
+        let path = env::temp_dir().join(format!("pmdm-sim-{}-{}", process::id(), name));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn pmdm_bytes(version: &GameVersion) -> Vec<u8> {
+        (0..version.pmdm_size).map(|i| i as u8).collect()
+    }
+
+    #[test]
+    fn region_dump_round_trips() {
+        let version = GameVersion::default_for_platform();
+        let dump = Dump {
+            version,
+            pmdm_address: 0x10000010,
+            regions: vec![
+                (0x10000000, [vec![0xaa; 0x10], pmdm_bytes(version)].concat()),
+                (0x20000000, vec![1, 2, 3]),
+            ],
+            state: ScriptState {
+                paused: true,
+                save_file: Some(vec![GameDataItem {
+                    name: "Item_Cook_A_01".to_string(),
+                    item_type: PouchItemType::Food,
+                    equipped: false,
+                    value: 1,
+                    data: Data { cook: CookData {
+                        health_recover: Guest::new(12), ..CookData::default()
+                    } },
+                }]),
+            },
+        };
+        let path = temp_path("region.bin");
+        write_dump(&path, &dump).unwrap();
+        let read = read_dump(&path, None);
+        fs::remove_file(&path).unwrap();
+
+        let read = read.unwrap();
+        assert_eq!(read.version.name, version.name);
+        assert_eq!(read.pmdm_address, dump.pmdm_address);
+        assert_eq!(read.regions, dump.regions);
+        assert!(read.state.paused);
+        let save_file = read.state.save_file.unwrap();
+        assert_eq!(save_file.len(), 1);
+        assert_eq!(save_file[0].name, "Item_Cook_A_01");
+        assert_eq!(save_file[0].item_type, PouchItemType::Food);
+        assert!(!save_file[0].equipped);
+        assert_eq!(save_file[0].value, 1);
+        assert_eq!(unsafe { save_file[0].data.cook.health_recover.get() }, 12);
+    }
+
+    #[test]
+    fn region_dump_without_pmdm_is_rejected() {
+        let dump = Dump {
+            version: GameVersion::default_for_platform(),
+            pmdm_address: 0x30000000,
+            regions: vec![(0x10000000, vec![0; 0x10])],
+            state: ScriptState::default(),
+        };
+        let path = temp_path("no-pmdm.bin");
+        write_dump(&path, &dump).unwrap();
+        let read = read_dump(&path, None);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(read, Err(SimError::BadDumpFile { .. })));
+    }
+
+    #[test]
+    fn legacy_dump_is_read() {
+        let version = GameVersion::default_for_platform();
+        let path = temp_path("legacy.bin");
+        fs::write(&path, [&0x10000000u64.to_le_bytes()[..], &pmdm_bytes(version)].concat())
+            .unwrap();
+        let read = read_dump(&path, Some(version.name));
+        fs::remove_file(&path).unwrap();
+
+        let read = read.unwrap();
+        assert_eq!(read.version.name, version.name);
+        assert_eq!(read.pmdm_address, 0x10000000);
+        assert_eq!(read.regions, [(0x10000000, pmdm_bytes(version))]);
+    }
+
+    #[test]
+    fn legacy_dump_sizes_are_checked() {
+        let version = GameVersion::default_for_platform();
+        let path = temp_path("short.bin");
+        fs::write(&path, [0; 4]).unwrap();
+        let short = read_dump(&path, None);
+        fs::write(&path, [0; 0x10]).unwrap();
+        let unknown = read_dump(&path, None);
+        let mismatch = read_dump(&path, Some(version.name));
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(short, Err(SimError::UnknownDumpSize { found: 4 })));
+        assert!(matches!(unknown, Err(SimError::UnknownDumpSize { found: 8 })));
+        assert!(matches!(mismatch, Err(SimError::DumpSizeMismatch { found: 8, .. })));
+    }
+
+    #[test]
+    fn dump_regions_out_of_range_are_rejected() {
+        let version = GameVersion::default_for_platform();
+        let path = temp_path("out-of-range.bin");
+        fs::write(&path, [&0xffffffffffffff00u64.to_le_bytes()[..], &pmdm_bytes(version)].concat())
+            .unwrap();
+        let legacy = read_dump(&path, Some(version.name));
+        write_dump(&path, &Dump {
+            version,
+            pmdm_address: 0x10000000,
+            regions: vec![(0x10000000, pmdm_bytes(version)), (ASLR_END - 0x4, vec![0; 0x8])],
+            state: ScriptState::default(),
+        }).unwrap();
+        let region = read_dump(&path, None);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(legacy, Err(SimError::BadDumpFile { .. })));
+        assert!(matches!(region, Err(SimError::BadDumpFile { .. })));
+    }
+
+    #[test]
+    fn inventory_file_is_read() {
+        let path = temp_path("inventory.json");
+        fs::write(&path, r#"[
+            {"name": "Item_Fruit_A", "type": "Material", "value": 5},
+            {"name": "Weapon_Sword_001", "type": "Sword", "value": 2200, "equipped": true,
+             "modifier": {"flags": 1, "value": 10}},
+            {"name": "Item_Cook_A_01", "type": "Food", "value": 1, "cook": {
+             "health_recover": 8, "effect_duration": 60, "sell_price": 10, "effect_id": 2,
+             "effect_level": 1.5}}
+        ]"#).unwrap();
+        let file = read_inventory(&path);
+        fs::write(&path, r#"[{"name": "Item_Fruit_A", "type": "Fruit", "value": 5}]"#).unwrap();
+        let bad_type = read_inventory(&path);
+        fs::write(&path, r#"[{"name": "Item_Fruit_A", "type": "Material"}]"#).unwrap();
+        let no_value = read_inventory(&path);
+        fs::remove_file(&path).unwrap();
+
+        let file = file.unwrap();
+        let fields: Vec<_> = file.iter().map(
+            |item| (item.name.as_str(), item.item_type, item.value, item.equipped)
+        ).collect();
+        assert_eq!(fields, [
+            ("Item_Fruit_A", PouchItemType::Material, 5, false),
+            ("Weapon_Sword_001", PouchItemType::Sword, 2200, true),
+            ("Item_Cook_A_01", PouchItemType::Food, 1, false),
+        ]);
+        let weapon = unsafe { file[1].data.weapon };
+        assert_eq!((weapon.modifier.get(), weapon.modifier_value.get()), (1, 10));
+        let cook = unsafe { file[2].data.cook };
+        assert_eq!(
+            (cook.health_recover.get(), cook.effect_duration.get(), cook.sell_price.get()),
+            (8, 60, 10)
+        );
+        assert_eq!((cook.effect_id.get(), cook.effect_level.get()), (2.0, 1.5));
+
+        assert!(matches!(bad_type, Err(SimError::BadInventoryFile(_))));
+        assert!(matches!(no_value, Err(SimError::BadInventoryFile(_))));
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::mem;
+
+use crate::error::*;
+use crate::mem::*;
+use crate::pmdm::*;
+use crate::types::*;
+
+// Invariant of PMDM broken by a dump or simulated state
+#[derive(Debug)]
+pub enum Violation {
+    // List offset does not lead from an item to its list_node
+    WrongOffset { list: ItemList, offset: i32, expected: i32 },
+    // Node could not be read while walking the list
+    UnreadableNode { list: ItemList, address: u64 },
+    // node.next.prev does not lead back to node
+    BrokenLink { list: ItemList, node: u64, next: u64, next_prev: u64 },
+    // Chain revisits a node before returning to start_end
+    Cycle { list: ItemList, node: u64 },
+    // Node is not the list_node of an item in item_lists.buffer
+    NodeOutsideBuffer { list: ItemList, node: u64 },
+    CountMismatch { list: ItemList, count: i32, linked: usize },
+    // List head does not point at the first item of its category in list1
+    WrongListHead { category: PouchCategory, found: u64, expected: u64 },
+    UnreadableListHead { category: PouchCategory, head: u64 },
+    DeadEquippedWeapon { index: usize, item: u64 },
+    DeadLastAddedItem { item: u64 },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::WrongOffset { list, offset, expected } => write!(
+                f, "{:?}: offset is {} instead of {}", list, offset, expected
+            ),
+            Self::UnreadableNode { list, address } => write!(
+                f, "{:?}: node 0x{:x} is unreadable", list, address
+            ),
+            Self::BrokenLink { list, node, next, next_prev } => write!(
+                f, "{:?}: node 0x{:x} links to 0x{:x}, which links back to 0x{:x}",
+                list, node, next, next_prev
+            ),
+            Self::Cycle { list, node } => write!(
+                f, "{:?}: node 0x{:x} is reached twice without returning to start", list, node
+            ),
+            Self::NodeOutsideBuffer { list, node } => write!(
+                f, "{:?}: node 0x{:x} is not in item_lists.buffer", list, node
+            ),
+            Self::CountMismatch { list, count, linked } => write!(
+                f, "{:?}: count is {} but {} items are linked", list, count, linked
+            ),
+            Self::WrongListHead { category, found, expected } => write!(
+                f, "list_heads[{}] leads to 0x{:x} instead of 0x{:x}",
+                category.raw(), found, expected
+            ),
+            Self::UnreadableListHead { category, head } => write!(
+                f, "list_heads[{}] points at unreadable 0x{:x}", category.raw(), head
+            ),
+            Self::DeadEquippedWeapon { index, item } => write!(
+                f, "equipped_weapons[{}] points at 0x{:x}, which is not in list1", index, item
+            ),
+            Self::DeadLastAddedItem { item } => write!(
+                f, "last_added_item points at 0x{:x}, which is not in list1", item
+            ),
+        }
+    }
+}
+
+// Every violation found by check_integrity
+#[derive(Debug, Default)]
+pub struct IntegrityReport {
+    pub violations: Vec<Violation>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool { self.violations.is_empty() }
+}
+
+impl fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_ok() { return writeln!(f, "No integrity violations"); }
+        writeln!(f, "{} integrity violations:", self.violations.len())?;
+        for violation in &self.violations { writeln!(f, "  {}", violation)?; }
+        Ok(())
+    }
+}
+
+impl PauseMenuDataMgr {
+    // Check list links, counts, list heads and item pointers without changing anything
+    pub fn check_integrity(
+        self: Pointer<Self>, memory: &Memory
+    ) -> Result<IntegrityReport, SimError> {
+        let mut report = IntegrityReport::default();
+        let list1_items = self.check_list(memory, ItemList::List1, &mut report)?;
+        self.check_list(memory, ItemList::List2, &mut report)?;
+
+        // Items past list1.count are not live, even if they are still linked
+        let pmdm = self.view(memory)?;
+        let live_count = pmdm.item_lists.list1.count.get().max(0) as usize;
+        let live_items = &list1_items[..list1_items.len().min(live_count)];
+
+        for category in (0..NUM_POUCH_CATEGORIES).map(PouchCategory::from_raw) {
+            let expected = live_items.iter().find(|item_ptr| {
+                item_ptr.view(memory).is_ok_and(|item| item.item_type.get().category() == category)
+            }).copied().unwrap_or(Pointer::NULLPTR);
+
+            let p_head = pmdm.list_heads.buffer[category.raw() as usize].get();
+            let found = if p_head == Pointer::NULLPTR { Pointer::NULLPTR } else {
+                match p_head.get(memory) {
+                    Ok(head) => head,
+                    Err(_) => {
+                        report.violations.push(
+                            Violation::UnreadableListHead { category, head: p_head.address }
+                        );
+                        continue;
+                    },
+                }
+            };
+            if found != expected {
+                report.violations.push(Violation::WrongListHead {
+                    category, found: found.address, expected: expected.address
+                });
+            }
+        }
+
+        for (index, weapon) in pmdm.equipped_weapons.buffer.iter().enumerate() {
+            let item = weapon.get();
+            if item != Pointer::NULLPTR && !live_items.contains(&item) {
+                report.violations.push(
+                    Violation::DeadEquippedWeapon { index, item: item.address }
+                );
+            }
+        }
+
+        let item = pmdm.last_added_item.get();
+        if item != Pointer::NULLPTR && !live_items.contains(&item) {
+            report.violations.push(Violation::DeadLastAddedItem { item: item.address });
+        }
+
+        Ok(report)
+    }
+
+    // Walk list from start_end, checking each link, and return the items reached
+    fn check_list(
+        self: Pointer<Self>, memory: &Memory, list: ItemList, report: &mut IntegrityReport
+    ) -> Result<Vec<Pointer<PouchItem>>, SimError> {
+        let list_ptr = match list {
+            ItemList::List1 => self.list1(),
+            ItemList::List2 => self.list2(),
+        };
+        let offset_list = list_ptr.view(memory)?;
+        let start_end = field!(list_ptr, start_end);
+
+        // Nodes are still walked with a wrong offset, and checked against the right one, they
+        // just won't map to items
+        let offset = offset_list.offset.get();
+        let expected = mem::offset_of!(PouchItem, list_node) as i32;
+        if offset != expected {
+            report.violations.push(Violation::WrongOffset { list, offset, expected });
+        }
+        let mut items = vec![];
+        let mut visited = BTreeSet::new();
+        let mut node = start_end;
+
+        // Keep walking past bad nodes while their links can be read, so the rest of the list
+        // and its count are still checked. Whether the whole chain was walked is returned.
+        let walked = loop {
+            let next = node.view(memory)?.next.get();
+            let next_prev = match next.view(memory) {
+                Ok(next_node) => next_node.prev.get(),
+                Err(_) => {
+                    report.violations.push(
+                        Violation::UnreadableNode { list, address: next.address }
+                    );
+                    break false;
+                },
+            };
+            if next_prev != node {
+                report.violations.push(Violation::BrokenLink {
+                    list, node: node.address, next: next.address, next_prev: next_prev.address
+                });
+            }
+            if next == start_end { break true; }
+
+            // Past the first repeated node the walk only goes around the cycle again
+            if !visited.insert(next.address) {
+                report.violations.push(Violation::Cycle { list, node: next.address });
+                break true;
+            }
+            let buffer_item = Pointer::<PouchItem>::new(next.address.wrapping_sub(expected as u64));
+            if self.item_index(buffer_item).is_none() {
+                report.violations.push(Violation::NodeOutsideBuffer { list, node: next.address });
+            }
+            items.push(offset_list.list_node_to_obj(next));
+            node = next;
+        };
+
+        let count = offset_list.count.get();
+        if walked && (count < 0 || count as usize != items.len()) {
+            report.violations.push(Violation::CountMismatch { list, count, linked: items.len() });
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pmdm::tests::*;
+
+    use super::*;
+
+    // PMDM holding a sword and an apple, with the items of list1 in order
+    fn two_item_pmdm() -> (Memory, Pointer<PauseMenuDataMgr>, [Pointer<PouchItem>; 2]) {
+        let (memory, this) = create_pmdm(vec![
+            item("Weapon_Sword_001", PouchItemType::Sword, 10),
+            item("Item_Fruit_A", PouchItemType::Material, 5),
+        ]);
+        let list1 = this.list1().view(&memory).unwrap();
+        let items = [list1.nth(&memory, 0).unwrap(), list1.nth(&memory, 1).unwrap()];
+        (memory, this, items)
+    }
+
+    fn violations(memory: &Memory, this: Pointer<PauseMenuDataMgr>) -> Vec<Violation> {
+        this.check_integrity(memory).unwrap().violations
+    }
+
+    #[test]
+    fn created_pmdm_is_ok() {
+        let (memory, this, _) = two_item_pmdm();
+        assert!(this.check_integrity(&memory).unwrap().is_ok());
+    }
+
+    #[test]
+    fn wrong_offset_still_walks_list() {
+        let (mut memory, this, _) = two_item_pmdm();
+        field!(this.list1(), offset).set(&mut memory, 0).unwrap();
+        field!(this.list1(), count).set(&mut memory, 3).unwrap();
+        let violations = violations(&memory, this);
+        assert!(matches!(violations[0], Violation::WrongOffset { offset: 0, .. }));
+        assert!(violations.iter().any(|violation| matches!(
+            violation, Violation::CountMismatch { list: ItemList::List1, count: 3, linked: 2 }
+        )), "{:?}", violations);
+        assert!(!violations.iter().any(
+            |violation| matches!(violation, Violation::NodeOutsideBuffer { .. })
+        ));
+    }
+
+    #[test]
+    fn unreadable_node_is_reported() {
+        let (mut memory, this, [_, apple]) = two_item_pmdm();
+        field!(apple, list_node.next).set(&mut memory, Pointer::new(0x4)).unwrap();
+        assert!(matches!(
+            violations(&memory, this)[..],
+            [Violation::UnreadableNode { list: ItemList::List1, address: 0x4 }, ..]
+        ));
+    }
+
+    #[test]
+    fn broken_link_is_reported() {
+        let (mut memory, this, [sword, apple]) = two_item_pmdm();
+        let apple_node = field!(apple, list_node);
+        field!(apple_node, prev).set(&mut memory, apple_node).unwrap();
+        assert!(matches!(
+            violations(&memory, this)[..],
+            [Violation::BrokenLink { list: ItemList::List1, node, next, next_prev }]
+                if node == field!(sword, list_node).address && next == apple_node.address
+                    && next_prev == apple_node.address
+        ));
+    }
+
+    #[test]
+    fn cycle_is_reported() {
+        let (mut memory, this, [_, apple]) = two_item_pmdm();
+        let apple_node = field!(apple, list_node);
+        field!(apple_node, next).set(&mut memory, apple_node).unwrap();
+        let violations = violations(&memory, this);
+        assert!(violations.iter().any(|violation| matches!(
+            violation,
+            Violation::Cycle { list: ItemList::List1, node } if *node == apple_node.address
+        )), "{:?}", violations);
+        assert!(!violations.iter().any(
+            |violation| matches!(violation, Violation::CountMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn node_outside_buffer_still_walks_list() {
+        // Link newly_added_item between the sword and the apple
+        let (mut memory, this, [sword, apple]) = two_item_pmdm();
+        let (sword_node, apple_node) = (field!(sword, list_node), field!(apple, list_node));
+        let stray_node = field!(this, newly_added_item.list_node);
+        field!(sword_node, next).set(&mut memory, stray_node).unwrap();
+        field!(stray_node, prev).set(&mut memory, sword_node).unwrap();
+        field!(stray_node, next).set(&mut memory, apple_node).unwrap();
+        field!(apple_node, prev).set(&mut memory, stray_node).unwrap();
+
+        // The apple is past the count now, so list heads and item pointers are off too
+        let violations = violations(&memory, this);
+        assert!(matches!(
+            violations[..],
+            [
+                Violation::NodeOutsideBuffer { list: ItemList::List1, node },
+                Violation::CountMismatch { list: ItemList::List1, count: 2, linked: 3 },
+                ..
+            ] if node == stray_node.address
+        ), "{:?}", violations);
+    }
+
+    #[test]
+    fn count_mismatch_is_reported() {
+        let (mut memory, this, _) = two_item_pmdm();
+        field!(this.list2(), count).set(&mut memory, 0).unwrap();
+        assert!(matches!(
+            violations(&memory, this)[..],
+            [Violation::CountMismatch { list: ItemList::List2, count: 0, linked }]
+                if linked == NUM_POUCH_ITEMS_MAX as usize - 2
+        ));
+    }
+
+    #[test]
+    fn list_heads_are_checked() {
+        let (mut memory, this, [sword, apple]) = two_item_pmdm();
+        let heads = field!(this, list_heads);
+        let material = PouchCategory::Material.raw() as usize;
+        let key_item = PouchCategory::KeyItem.raw() as usize;
+        field!(heads, buffer[material]).set(&mut memory, field!(this, tabs.buffer[0])).unwrap();
+        field!(heads, buffer[key_item]).set(&mut memory, Pointer::new(0x4)).unwrap();
+        assert!(matches!(
+            violations(&memory, this)[..],
+            [
+                Violation::WrongListHead { category: PouchCategory::Material, found, expected },
+                Violation::UnreadableListHead { category: PouchCategory::KeyItem, head: 0x4 },
+            ] if found == sword.address && expected == apple.address
+        ));
+    }
+
+    #[test]
+    fn dead_item_pointers_are_reported() {
+        let (mut memory, this, _) = two_item_pmdm();
+        let free_item = this.list2().view(&memory).unwrap().front();
+        field!(this, equipped_weapons.buffer[1]).set(&mut memory, free_item).unwrap();
+        field!(this, last_added_item).set(&mut memory, free_item).unwrap();
+        assert!(matches!(
+            violations(&memory, this)[..],
+            [
+                Violation::DeadEquippedWeapon { index: 1, item },
+                Violation::DeadLastAddedItem { item: last_added_item },
+            ] if item == free_item.address && last_added_item == free_item.address
+        ));
+    }
+}
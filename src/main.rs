@@ -1,40 +1,197 @@
 #![allow(incomplete_features)]
+#![feature(arbitrary_self_types)]
 #![feature(generic_const_exprs)]
-#![feature(map_try_insert)]
 #![feature(offset_of_nested)]
 #![feature(slice_ptr_get)]
 
+mod diff;
+mod error;
 mod fs;
+mod integrity;
 mod mem;
+mod platform;
 mod pmdm;
+mod render;
+mod script;
+mod symbol;
 mod traits;
 mod types;
+mod version;
 
+use std::env;
+use std::io::{stdin, IsTerminal};
+use std::ops::Range;
+use std::process;
+
+use diff::*;
+use error::*;
 use fs::*;
 use mem::*;
+use platform::*;
 use pmdm::*;
-use types::*;
-
-const PMDM_BASE_AOC: u64 = 0xa982c8b0;
+use render::*;
+use script::*;
+use symbol::*;
+use version::*;
 
 fn main() {
-    // Initialize PMDM
-    let (pmdm_address, pmdm_data) = read_dump("pmdm.bin").unwrap();
-    println!("PauseMenuDataMgr::sInstance == 0x{:x}", pmdm_address);
-    println!("Heap base: 0x{:x}", pmdm_address - PMDM_BASE_AOC);
-    let mut memory = Memory::init(pmdm_address, pmdm_data);
-    let pmdm_ptr = Pointer::<PauseMenuDataMgr>::new(pmdm_address);
-    let mut pmdm = pmdm_ptr.read(&memory).unwrap();
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
+
+// Parse a hexadecimal address like 0x3000000000
+fn parse_address(text: &str) -> Option<u64> {
+    u64::from_str_radix(text.strip_prefix("0x")?, 16).ok()
+}
+
+// Parse a watched range given as an address, an address range like 0x1000-0x1008 or a field
+// path like item_lists.list2.count
+fn parse_watch_range(
+    spec: &str, pmdm_ptr: Pointer<PauseMenuDataMgr>
+) -> Result<Range<u64>, SimError> {
+    let bad_watchpoint = || SimError::BadWatchpoint { spec: spec.to_string() };
+
+    if !spec.starts_with("0x") { return resolve(pmdm_ptr, spec).ok_or_else(bad_watchpoint); }
+    match spec.split_once('-') {
+        Some((start, end)) => {
+            let (start, end) = (parse_address(start), parse_address(end));
+            start.zip(end).filter(|(start, end)| start < end).map(|(start, end)| start..end)
+        },
+        None => parse_address(spec).map(|address| address..address + 1),
+    }.ok_or_else(bad_watchpoint)
+}
+
+fn run() -> Result<(), SimError> {
+    // Parse arguments
+    let mut inventory_path = None;
+    let mut script_path = None;
+    let mut version_name = None;
+    let mut heap_base = SYNTHETIC_HEAP_BASE;
+    let mut dump_path = "pmdm.bin".to_string();
+    let mut snapshot_path = None;
+    let mut check = false;
+    let mut dot_path = None;
+    let mut diff_path = None;
+    let mut trace_path = None;
+    let mut watch_specs = vec![];
+    let mut deref_policy = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--allow" => deref_policy = Some(DerefPolicy::Allow),
+            "--deny" => deref_policy = Some(DerefPolicy::Deny),
+            "--log" => deref_policy = Some(DerefPolicy::LogAndAllow),
+            "--script" => script_path = args.next(),
+            "--game-version" => version_name = args.next(),
+            "--heap-base" => {
+                let text = args.next().unwrap_or_default();
+                heap_base = parse_address(&text).ok_or(SimError::BadHeapBase { text })?;
+            },
+            "--dump" => if let Some(path) = args.next() { dump_path = path; },
+            "--snapshot" => snapshot_path = args.next(),
+            "--check" => check = true,
+            "--dot" => dot_path = args.next(),
+            "--diff" => diff_path = args.next(),
+            "--trace" => trace_path = args.next(),
+            "--watch" => watch_specs.extend(args.next().map(|spec| (spec, false))),
+            "--watch-stop" => watch_specs.extend(args.next().map(|spec| (spec, true))),
+            _ => inventory_path = Some(arg),
+        }
+    }
+
+    // Initialize PMDM from an inventory description if given, otherwise from a dump
+    let (version, mut memory, pmdm_ptr, mut state) = if let Some(path) = inventory_path {
+        let version = match &version_name {
+            Some(name) => GameVersion::find(name)?,
+            None => GameVersion::default_for_platform(),
+        };
+        version.check_platform()?;
+        let (memory, pmdm_ptr) = PauseMenuDataMgr::create(
+            heap_base, version, read_inventory(&path)?
+        )?;
+        (version, memory, pmdm_ptr, ScriptState::default())
+    } else {
+        let dump = read_dump(&dump_path, version_name.as_deref())?;
+        let pmdm_ptr = Pointer::<PauseMenuDataMgr>::new(dump.pmdm_address);
+        (dump.version, Memory::init(dump.regions), pmdm_ptr, dump.state)
+    };
+    // Only prompt when someone is there to answer, scripts and piped input are allowed through
+    // and --log shows the calls
+    if let Some(policy) = deref_policy {
+        memory.set_deref_policy(policy);
+    } else if script_path.is_none() && stdin().is_terminal() {
+        memory.set_deref_policy(DerefPolicy::Interactive(Box::new(prompt_deref)));
+    }
+    memory.set_symbolizer(Box::new(move |address| field_path(pmdm_ptr, address)));
+    println!("Game version: {}", version.name);
+    println!("PauseMenuDataMgr::sInstance == 0x{:x}", pmdm_ptr.address);
+    // A PMDM below the offset means the dump is of another version, so there is no heap base
+    if let Some(offset) = version.pmdm_heap_offset
+        && let Some(heap_base) = pmdm_ptr.address.checked_sub(offset)
+    {
+        println!("Heap base: 0x{:x}", heap_base);
+    }
 
     // Initialize translations
-    let translations = read_translations("botw_names.json").unwrap();
-
-    let list1 = pmdm.item_lists.list1;
-    let first_item: Box<PouchItem> = (
-        list1.start_end.next.to_ne() - list1.offset as u64
-    ).cast().read(&memory).unwrap();
-    let actor_name = first_item.name.to_string();
-    println!("firstItem: {}", if let Some(name) = translate_name(
-        actor_name.as_str(), translations
-    ) { name } else { actor_name });
+    let translations = read_translations("botw_names.json")?;
+
+    // Check first, as the inventory is rendered trusting the state the check may flag
+    if check { print!("{}", pmdm_ptr.check_integrity(&memory)?); }
+    print!("{}", render_inventory(&memory, pmdm_ptr, &translations)?);
+
+    // Check writes if anything is watched, keeping them only if a trace was asked for
+    if trace_path.is_some() || !watch_specs.is_empty() {
+        let mut watchpoints = vec![];
+        for (spec, stop) in watch_specs {
+            let range = parse_watch_range(&spec, pmdm_ptr)?;
+            let action = if stop { WatchAction::Stop } else {
+                WatchAction::Callback(Box::new(|write| println!("Watchpoint hit by {}", write)))
+            };
+            watchpoints.push(Watchpoint { range, action });
+        }
+        memory.set_tracer(Tracer::new(watchpoints, trace_path.is_some()));
+    }
+
+    let result = match script_path {
+        Some(path) => run_script(
+            &mut memory, pmdm_ptr, &read_script(&path)?, &translations, &mut state
+        ),
+        None => Ok(Outcome::Done(())),
+    };
+
+    // Write the trace even if a watchpoint stopped the run
+    if let Some(path) = trace_path && let Some(tracer) = memory.tracer() {
+        write_trace(&path, tracer)?;
+        println!("Trace of {} writes written to {}", tracer.writes.len(), path);
+    }
+    let outcome = result?;
+
+    // Snapshot the final state, frozen or not, so it can be resumed with --dump
+    if let Some(path) = snapshot_path {
+        write_dump(&path, &Dump {
+            version,
+            pmdm_address: pmdm_ptr.address,
+            regions: memory.regions().map(|(address, data)| (address, data.to_vec())).collect(),
+            state,
+        })?;
+        println!("Snapshot written to {}", path);
+    }
+    // Compare another dump or snapshot against the final state
+    if let Some(path) = diff_path {
+        let other = Memory::init(read_dump(&path, Some(version.name))?.regions);
+        let differences = diff_memory(&other, &memory, pmdm_ptr);
+        println!("{} differences from {}:", differences.len(), path);
+        for difference in differences { println!("  {}", difference); }
+    }
+    if let Some(path) = dot_path {
+        std::fs::write(&path, render_dot(&memory, pmdm_ptr, &translations)?)?;
+        println!("Graph written to {}", path);
+    }
+
+    match outcome {
+        Outcome::Done(()) => Ok(()),
+        Outcome::Frozen { list, cycle_nodes } => Err(SimError::GameFreeze { list, cycle_nodes }),
+    }
 }
@@ -1,134 +1,532 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::io::{stdin, stdout, Write};
 use std::marker::PhantomData;
-use std::mem;
-use std::ops;
+use std::mem::{self, MaybeUninit};
+use std::ops::{self, Range};
+use std::ptr;
+use std::slice;
+
+use crate::error::*;
+use crate::platform::*;
+
+// Asks whether to dereference an address, given the field making the call if it is known
+pub type DerefPrompt = Box<dyn Fn(u64, Option<&str>) -> Result<bool, SimError>>;
+
+// Decides whether virtual calls through guest pointers go ahead
+pub enum DerefPolicy {
+    Allow,
+    Deny,
+    LogAndAllow,
+    Interactive(DerefPrompt),
+}
+
+// Ask on stdin whether to dereference address
+pub fn prompt_deref(address: u64, caller: Option<&str>) -> Result<bool, SimError> {
+    match caller {
+        Some(caller) => print!(
+            "Attempted to dereference 0x{:x} from {}, proceed? (Y/n) ", address, caller
+        ),
+        None => print!("Attempted to dereference 0x{:x}, proceed? (Y/n) ", address),
+    }
+    stdout().flush()?;
+    let mut proceed = String::new();
+    stdin().read_line(&mut proceed)?;
+
+    Ok(!matches!(
+        proceed.chars().next().and_then(|choice| choice.to_lowercase().next()), Some('n')
+    ))
+}
+
+#[derive(Clone, Copy, Default)]
+#[repr(C, align(16))]
+struct Chunk([u8; 16]);
+
+// Block of guest memory, laid out so host addresses keep the alignment of guest addresses
+#[derive(Clone)]
+struct Block {
+    offset: usize,
+    len: usize,
+    chunks: Vec<Chunk>,
+}
+
+impl Block {
+    fn new(start: u64, data: &[u8]) -> Self {
+        let mut block = Self {
+            offset: (start % mem::align_of::<Chunk>() as u64) as usize, len: 0, chunks: vec![]
+        };
+        block.extend_from_slice(data);
+        block
+    }
+
+    fn len(&self) -> usize { self.len }
 
-const ASLR_START: u64 = 0x8000000;
-const ASLR_END: u64 = 0x8000000000;
+    fn bytes(&self) -> &[u8] { unsafe { slice::from_raw_parts(
+        self.chunks.as_ptr().cast::<u8>().add(self.offset), self.len
+    ) } }
 
-pub struct Memory { memory: HashMap<u64, Vec<u8>> }
+    fn bytes_mut(&mut self) -> &mut [u8] { unsafe { slice::from_raw_parts_mut(
+        self.chunks.as_mut_ptr().cast::<u8>().add(self.offset), self.len
+    ) } }
+
+    // Grow block, filling it with zeroes
+    fn resize(&mut self, len: usize) {
+        let chunk_size = mem::size_of::<Chunk>();
+        self.chunks.resize((self.offset + len).div_ceil(chunk_size), Chunk::default());
+        self.len = len;
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) {
+        let len = self.len;
+        self.resize(len + data.len());
+        self.bytes_mut()[len..].copy_from_slice(data);
+    }
+}
+
+// Run of bytes changed by a delta
+struct Change {
+    address: u64,
+    old: Vec<u8>,
+    new: Vec<u8>,
+}
+
+// Bytes changed while recording, enough to undo and redo the writes without copying memory
+#[derive(Default)]
+pub struct Delta {
+    changes: Vec<Change>,
+}
+
+// Write recorded by a tracer. Bytes that were uninitialized before read as zero in old.
+#[derive(Clone)]
+pub struct TracedWrite {
+    pub address: u64,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+    // Action the write was made by
+    pub action: String,
+    pub symbol: Option<String>,
+}
+
+impl fmt::Display for TracedWrite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hex = |bytes: &[u8]| -> String {
+            bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+        };
+        write!(f, "{}: 0x{:x}", self.action, self.address)?;
+        if let Some(symbol) = &self.symbol { write!(f, " {}", symbol)?; }
+        write!(f, ": {} -> {}", hex(&self.old), hex(&self.new))
+    }
+}
+
+// What happens when a write touches a watched range
+pub enum WatchAction {
+    Stop,
+    Callback(Box<dyn Fn(&TracedWrite)>),
+}
+
+pub struct Watchpoint {
+    pub range: Range<u64>,
+    pub action: WatchAction,
+}
+
+// Checks writes against watchpoints, and records every write if asked to
+#[derive(Default)]
+pub struct Tracer {
+    pub writes: Vec<TracedWrite>,
+    pub watchpoints: Vec<Watchpoint>,
+    // Action to attribute writes to
+    pub action: String,
+    // Whether to keep writes, which only a trace file needs
+    record_writes: bool,
+    // Write that hit a stopping watchpoint, until the run is stopped
+    stop: Option<TracedWrite>,
+}
+
+impl Tracer {
+    pub fn new(watchpoints: Vec<Watchpoint>, record_writes: bool) -> Self {
+        Self { watchpoints, record_writes, ..Default::default() }
+    }
+
+    fn record(&mut self, write: TracedWrite) {
+        let end = write.address + write.new.len() as u64;
+        let mut stop = false;
+        for watchpoint in &self.watchpoints {
+            if write.address >= watchpoint.range.end || end <= watchpoint.range.start { continue; }
+            match &watchpoint.action {
+                WatchAction::Stop => stop = true,
+                WatchAction::Callback(callback) => callback(&write),
+            }
+        }
+        if stop { self.stop = Some(write.clone()); }
+        if self.record_writes { self.writes.push(write); }
+    }
+}
+
+// Names an address as a field path, if it is part of a known object
+pub type Symbolizer = Box<dyn Fn(u64) -> Option<String>>;
+
+pub struct Memory {
+    memory: BTreeMap<u64, Block>,
+    deref_policy: DerefPolicy,
+    // Names addresses in errors and deref prompts
+    symbolizer: Option<Symbolizer>,
+    // Contents of each byte before its first write since recording began
+    journal: Option<BTreeMap<u64, u8>>,
+    tracer: Option<Tracer>,
+}
 
 impl Memory {
-    // Initialize memory with data
-    pub fn init(address: u64, data: Vec<u8>) -> Self { Self {
-        memory: HashMap::from([(address, data)])
-    } }
+    // Initialize memory with regions of data, later regions overwriting earlier ones. Virtual
+    // calls are allowed until another policy is set.
+    pub fn init(regions: Vec<(u64, Vec<u8>)>) -> Self {
+        let mut memory = Self {
+            memory: BTreeMap::new(),
+            deref_policy: DerefPolicy::Allow,
+            symbolizer: None,
+            journal: None,
+            tracer: None,
+        };
+        for (address, data) in regions { memory.write_bytes(address, &data); }
+        memory
+    }
 
-    // Read object from memory
-    pub fn read<T>(&self, address: u64) -> Result<Box<T>, String> {
-        let end = address + mem::size_of::<T>() as u64;
+    pub fn set_deref_policy(&mut self, policy: DerefPolicy) { self.deref_policy = policy; }
+
+    pub fn set_symbolizer(&mut self, symbolizer: Symbolizer) {
+        self.symbolizer = Some(symbolizer);
+    }
+
+    // Trace writes from now on
+    pub fn set_tracer(&mut self, tracer: Tracer) { self.tracer = Some(tracer); }
+
+    pub fn tracer(&self) -> Option<&Tracer> { self.tracer.as_ref() }
+
+    // Attribute following writes to action, if tracing
+    pub fn set_trace_action(&mut self, action: String) {
+        if let Some(tracer) = &mut self.tracer { tracer.action = action; }
+    }
+
+    // Stop the run if a write hit a stopping watchpoint
+    fn check_watchpoints(&mut self) -> Result<(), SimError> {
+        let Some(tracer) = &mut self.tracer else { return Ok(()); };
+        let Some(write) = tracer.stop.take() else { return Ok(()); };
+        Err(SimError::WatchpointHit {
+            action: write.action, address: write.address, symbol: write.symbol
+        })
+    }
+
+    // Name the field at address, if the symbolizer knows it
+    pub fn symbolize(&self, address: u64) -> Option<String> {
+        self.symbolizer.as_ref().and_then(|symbolizer| symbolizer(address))
+    }
+
+    // Read byte, if it is initialized
+    pub fn byte(&self, address: u64) -> Option<u8> {
+        let (start, block) = self.find_block(address, address.saturating_add(1))?;
+        Some(block.bytes()[(address - start) as usize])
+    }
+
+    // Initialized regions of memory in address order
+    pub fn regions(&self) -> impl Iterator<Item = (u64, &[u8])> {
+        self.memory.iter().map(|(&start, block)| (start, block.bytes()))
+    }
+
+    // Start recording writes for a delta
+    pub fn begin_delta(&mut self) { self.journal = Some(BTreeMap::new()); }
+
+    // Stop recording and collect the bytes that changed since begin_delta
+    pub fn end_delta(&mut self) -> Delta {
+        let mut delta = Delta::default();
+        for (address, old) in self.journal.take().unwrap_or_default() {
+            let new = self.byte(address).unwrap();
+            if new == old { continue; }
+
+            match delta.changes.last_mut() {
+                Some(change) if change.address + change.old.len() as u64 == address => {
+                    change.old.push(old);
+                    change.new.push(new);
+                },
+                _ => delta.changes.push(Change { address, old: vec![old], new: vec![new] }),
+            }
+        }
+        delta
+    }
+
+    // Restore the bytes a delta changed. Bytes that were uninitialized before stay zeroed.
+    pub fn undo(&mut self, delta: &Delta) -> Result<(), SimError> {
+        for change in delta.changes.iter().rev() { self.write_bytes(change.address, &change.old); }
+        self.check_watchpoints()
+    }
+
+    // Apply the changes of a delta again
+    pub fn redo(&mut self, delta: &Delta) -> Result<(), SimError> {
+        for change in &delta.changes { self.write_bytes(change.address, &change.new); }
+        self.check_watchpoints()
+    }
+
+    // Check whether a virtual call through address by the object at caller may go ahead
+    pub fn call(&self, address: u64, caller: u64) -> Result<(), SimError> {
+        let symbol = self.symbolize(caller);
+        let allowed = match &self.deref_policy {
+            DerefPolicy::Allow => true,
+            DerefPolicy::Deny => false,
+            DerefPolicy::LogAndAllow => {
+                match &symbol {
+                    Some(symbol) => println!("Dereferenced 0x{:x} from {}", address, symbol),
+                    None => println!("Dereferenced 0x{:x}", address),
+                }
+                true
+            },
+            DerefPolicy::Interactive(callback) => callback(address, symbol.as_deref())?,
+        };
+
+        if allowed { Ok(()) } else { Err(SimError::DerefDenied { address, symbol }) }
+    }
+
+    // Find block containing address range
+    fn find_block(&self, address: u64, end: u64) -> Option<(u64, &Block)> {
+        let (&start, block) = self.memory.range(..=address).next_back()?;
+        (end - start <= block.len() as u64).then_some((start, block))
+    }
+
+    // Borrow object in place
+    pub fn view<T>(&self, address: u64) -> Result<&T, SimError> {
+        // Saturate so stray addresses near the top fail the range check instead of wrapping
+        let end = address.saturating_add(mem::size_of::<T>() as u64);
 
         // Check if object is in ASLR range
         if address < ASLR_START || end > ASLR_END {
-            return Err(format!("Address range 0x{:x}-0x{:x} is outside ASLR range", address, end));
+            return Err(SimError::OutOfAslrRange {
+                range: address..end, symbol: self.symbolize(address)
+            });
         }
 
-        // Find block containing address range
-        let (start, block) = self.memory.iter().find(
-            |&(start, block)| *start <= address && end - *start <= block.len() as u64
-        ).ok_or(format!("Uninitialized memory in range 0x{:x}-0x{:x}", address, end))?;
+        // Blocks keep guest alignment, so an aligned guest address is aligned on the host
+        if !address.is_multiple_of(mem::align_of::<T>() as u64) {
+            return Err(SimError::Misaligned { address, symbol: self.symbolize(address) });
+        }
 
-        // Read and box object
-        Ok(unsafe { Box::from_raw(mem::transmute::<*mut u8, *mut T>(Box::into_raw(block[
-            (address - *start) as usize..(end - *start) as usize
-        ].to_vec().into_boxed_slice()).as_mut_ptr())) })
+        let (start, block) = self.find_block(address, end).ok_or_else(
+            || SimError::UninitializedRead { range: address..end, symbol: self.symbolize(address) }
+        )?;
+        Ok(unsafe { &*block.bytes()[(address - start) as usize..].as_ptr().cast::<T>() })
     }
 
     // Write object to memory
-    pub fn write<T>(&mut self, address: u64, object: Box<T>) -> Result<(), String> {
-        let end = address + mem::size_of::<T>() as u64;
+    pub fn write<T>(&mut self, address: u64, object: Box<T>) -> Result<(), SimError> {
+        let end = address.saturating_add(mem::size_of::<T>() as u64);
 
         // Check if object is in ASLR range
         if address < ASLR_START || end > ASLR_END {
-            return Err(format!("Address range 0x{:x}-0x{:x} is outside ASLR range", address, end));
+            return Err(SimError::OutOfAslrRange {
+                range: address..end, symbol: self.symbolize(address)
+            });
         }
 
-        // Find block containing address range, if it exists
-        let block = match self.memory.iter_mut().find(
-            |(start, block)| **start <= address && end - **start <= block.len() as u64
-        ).ok_or(()) {
-            Ok(block) => block,
-            Err(_) => {
-                // Remove blocks fully enclosed in address range
-                self.memory.retain(|&start, block| !(
-                    address < start && start < end && start + block.len() as u64 <= end
-                ));
-
-                // Find remainder of block containing address range
-                // end and remove block, if it exists
-                let next_block = if let Some(block) = self.memory.clone().iter().find(
-                    |&(start, block)| address < *start && *start <= end
-                    && end < *start + block.len() as u64
-                ) { &self.memory.remove(block.0).unwrap()[(end - *block.0) as usize..] } else {
-                    &[]
-                };
-
-                // Find block containing or adjacent to address, if it exists
-                match self.memory.iter_mut().find(
-                    |(start, block)| **start <= address && address <= **start + block.len() as u64
-                ).ok_or(()) {
-                    Ok(block) => {
-                        // Resize block
-                        let block_remainder = end - *block.0 - block.1.len() as u64;
-                        block.1.resize_with(
-                            block.1.len() + block_remainder as usize + next_block.len(),
-                            Default::default
-                        );
+        self.write_bytes(address, unsafe { slice::from_raw_parts(
+            ptr::from_ref(object.as_ref()).cast::<u8>(), mem::size_of::<T>()
+        ) });
+        self.check_watchpoints()
+    }
 
-                        // Write next block data
-                        block.1[(end - block.0) as usize..].copy_from_slice(next_block);
-                        block
-                    },
-                    Err(_) => {
-                        // Create block
-                        let block = self.memory.try_insert(address, vec![
-                            Default::default(); mem::size_of::<T>() + next_block.len()
-                        ]).unwrap();
-
-                        // Write next block data
-                        block[mem::size_of::<T>()..].copy_from_slice(next_block);
-                        (&address, block)
+    // Write raw bytes to memory
+    fn write_bytes(&mut self, address: u64, data: &[u8]) {
+        let end = address + data.len() as u64;
+        let symbol = if self.tracer.is_some() { self.symbolize(address) } else { None };
+
+        // Find block containing address range, merging blocks overlapping or adjacent to it
+        // into the block before address if there is none
+        let start = match self.find_block(address, end) {
+            Some((start, _)) => start,
+            None => {
+                let (start, mut block) = match self.memory.range(..=address).next_back() {
+                    Some((&start, block)) if start + block.len() as u64 >= address => {
+                        (start, self.memory.remove(&start).unwrap())
                     },
+                    _ => (address, Block::new(address, &[])),
+                };
+                block.resize((end - start) as usize);
+
+                // Append the part of each following block that lies past end
+                let next_starts: Vec<u64> = self.memory.range(address..=end).map(
+                    |(&next_start, _)| next_start
+                ).collect();
+                for next_start in next_starts {
+                    let next_block = self.memory.remove(&next_start).unwrap();
+                    let next_end = next_start + next_block.len() as u64;
+                    if next_end > end {
+                        block.extend_from_slice(
+                            &next_block.bytes()[(end - next_start) as usize..]
+                        );
+                    }
                 }
+
+                self.memory.insert(start, block);
+                start
             },
         };
+        let block = self.memory.get_mut(&start).unwrap();
 
-        // Write object
-        unsafe { block.1[
-            (address - *block.0) as usize..(end - *block.0) as usize
-        ].as_mut_ptr().copy_from(mem::transmute(Box::into_raw(object)), mem::size_of::<T>()); }
-
-        Ok(())
+        let range = (address - start) as usize..(end - start) as usize;
+        if let Some(journal) = &mut self.journal {
+            for (i, &old) in block.bytes()[range.clone()].iter().enumerate() {
+                journal.entry(address + i as u64).or_insert(old);
+            }
+        }
+        if let Some(tracer) = &mut self.tracer {
+            tracer.record(TracedWrite {
+                address,
+                old: block.bytes()[range.clone()].to_vec(),
+                new: data.to_vec(),
+                action: tracer.action.clone(),
+                symbol,
+            });
+        }
+        block.bytes_mut()[range].copy_from_slice(data);
     }
 }
 
-#[derive(Clone, Copy, Default)]
+// Value as the guest keeps it in memory, in the platform's byte order and pointer width
+pub trait GuestValue: Copy {
+    type Repr: Copy + PartialEq;
+
+    fn to_guest(self) -> Self::Repr;
+    fn from_guest(repr: Self::Repr) -> Self;
+}
+
+macro_rules! impl_guest_value {
+    ($($int:ty),*) => { $(impl GuestValue for $int {
+        type Repr = Self;
+
+        fn to_guest(self) -> Self { if BIG_ENDIAN { self.to_be() } else { self.to_le() } }
+        fn from_guest(repr: Self) -> Self {
+            if BIG_ENDIAN { <$int>::from_be(repr) } else { <$int>::from_le(repr) }
+        }
+    })* };
+}
+
+impl_guest_value!(i8, u8, i32, u32, i64, u64);
+
+// Dumps may hold any byte where a bool belongs, so it is kept as one and decoded like C++
+impl GuestValue for bool {
+    type Repr = u8;
+
+    fn to_guest(self) -> u8 { self as u8 }
+    fn from_guest(repr: u8) -> Self { repr != 0 }
+}
+
+impl GuestValue for f32 {
+    type Repr = u32;
+
+    fn to_guest(self) -> u32 { self.to_bits().to_guest() }
+    fn from_guest(repr: u32) -> Self { Self::from_bits(u32::from_guest(repr)) }
+}
+
+impl<T> GuestValue for Pointer<T> {
+    type Repr = Address;
+
+    fn to_guest(self) -> Address { (self.address as Address).to_guest() }
+    // Address is already u64 on the Switch, so the conversion only widens on the Wii U
+    #[allow(clippy::useless_conversion)]
+    fn from_guest(repr: Address) -> Self { Self::new(u64::from(Address::from_guest(repr))) }
+}
+
+// Guest struct field, only reachable through get and set so the conversion can't be
+// forgotten. Single byte integers look the same on every platform and stay unwrapped.
+#[derive(Clone, Copy, PartialEq)]
+#[repr(transparent)]
+pub struct Guest<T: GuestValue>(T::Repr);
+
+impl<T: GuestValue> Guest<T> {
+    pub fn new(value: T) -> Self { Self(value.to_guest()) }
+
+    pub fn get(&self) -> T { T::from_guest(self.0) }
+
+    pub fn set(&mut self, value: T) { self.0 = value.to_guest(); }
+}
+
+impl<T: GuestValue + Default> Default for Guest<T> {
+    fn default() -> Self { Self::new(T::default()) }
+}
+
+// Enum the guest stores as an i32, which may hold values outside its variants
+pub trait GuestEnum: Copy + TryFrom<i32, Error = i32> {
+    fn raw(self) -> i32;
+    fn unknown(value: i32) -> Self;
+
+    // Decode raw value, keeping out-of-range values as unknown
+    fn from_raw(value: i32) -> Self { Self::try_from(value).unwrap_or_else(Self::unknown) }
+}
+
+// Guest enum field kept as its raw value, so garbage in a glitched slot decodes to
+// unknown instead of an invalid enum
+#[derive(Clone, Copy, PartialEq)]
+#[repr(transparent)]
+pub struct RawEnum<E> {
+    value: Guest<i32>,
+    phantom: PhantomData<E>,
+}
+
+impl<E: GuestEnum> RawEnum<E> {
+    pub fn new(value: E) -> Self { Self { value: Guest::new(value.raw()), phantom: PhantomData } }
+
+    pub fn get(&self) -> E { E::from_raw(self.value.get()) }
+}
+
+impl<E: GuestEnum + Default> Default for RawEnum<E> {
+    fn default() -> Self { Self::new(E::default()) }
+}
+
 #[repr(C)]
 pub struct Pointer<T = u8> {
     pub address: u64,
     phantom: PhantomData<T>,
 }
 
+impl<T> Clone for Pointer<T> { fn clone(&self) -> Self { *self } }
+
+impl<T> Copy for Pointer<T> {}
+
+impl<T> Default for Pointer<T> { fn default() -> Self { Self::NULLPTR } }
+
 impl<T> Pointer<T> {
     // Create pointer to address
     pub fn new(address: u64) -> Self { Self { address, phantom: PhantomData } }
 
-    // Dereference and read from pointer
-    pub fn read(&self, memory: &Memory) -> Result<Box<T>, String> { memory.read(self.address) }
+    // Borrow object behind pointer in place, without copying it out of memory
+    pub fn view<'a>(&self, memory: &'a Memory) -> Result<&'a T, SimError> {
+        memory.view(self.address)
+    }
 
     // Dereference and write to pointer
-    pub fn write(&self, memory: &mut Memory, object: Box<T>) -> Result<(), String> {
+    pub fn write(&self, memory: &mut Memory, object: Box<T>) -> Result<(), SimError> {
         memory.write(self.address, object)
     }
 
-    // Convert to native endian
-    pub fn to_ne(&self) -> Self { Self {
-        address: u64::from_le(self.address), phantom: PhantomData
-    } }
+    // Dereference pointer for a virtual call by the object at caller
+    pub fn call(&self, memory: &Memory, caller: u64) -> Result<(), SimError> {
+        let end = self.address.saturating_add(mem::size_of::<T>() as u64);
+        if self.address < ASLR_START || end > ASLR_END {
+            return Err(SimError::OutOfAslrRange {
+                range: self.address..end, symbol: memory.symbolize(caller)
+            });
+        }
 
-    // Convert to little-endian
-    pub fn to_le(&self) -> Self { Self { address: self.address.to_le(), phantom: PhantomData } }
+        memory.call(self.address, caller)
+    }
+
+    // Project pointer onto a place inside the object, usually through field!. The projection
+    // gets a pointer to uninitialized memory, so it may only compute addresses, never read.
+    pub fn project<U>(&self, projection: impl FnOnce(*const T) -> *const U) -> Pointer<U> {
+        let object = MaybeUninit::<T>::uninit();
+        let base = object.as_ptr();
+        let offset = projection(base).addr().wrapping_sub(base.addr());
+        assert!(
+            offset.checked_add(mem::size_of::<U>()).is_some_and(|end| end <= mem::size_of::<T>()),
+            "projection must stay inside the object"
+        );
+        Pointer::new(self.address.wrapping_add(offset as u64))
+    }
 
     // Cast pointer type
     pub fn cast<U>(&self) -> Pointer<U> { Pointer { address: self.address, phantom: PhantomData } }
@@ -136,47 +534,181 @@ impl<T> Pointer<T> {
     pub const NULLPTR: Self = Self { address: 0u64, phantom: PhantomData };
 }
 
+// Project pointer onto a field of the object, e.g. field!(this, list_node.next)
+macro_rules! field {
+    ($ptr:expr, $($path:tt)+) => {
+        $ptr.project(|p| unsafe { &raw const (*p).$($path)+ })
+    };
+}
+
+pub(crate) use field;
+
+impl<T: GuestValue> Pointer<T> {
+    // Read value, converting from the guest representation
+    pub fn get(&self, memory: &Memory) -> Result<T, SimError> {
+        Ok(T::from_guest(*self.cast::<T::Repr>().view(memory)?))
+    }
+
+    // Write value, converting to the guest representation
+    pub fn set(&self, memory: &mut Memory, value: T) -> Result<(), SimError> {
+        self.cast().write(memory, Box::new(value.to_guest()))
+    }
+}
+
+impl<T: GuestValue> Pointer<Guest<T>> {
+    // Read field value
+    pub fn get(&self, memory: &Memory) -> Result<T, SimError> { Ok(self.view(memory)?.get()) }
+
+    // Write field value
+    pub fn set(&self, memory: &mut Memory, value: T) -> Result<(), SimError> {
+        self.write(memory, Box::new(Guest::new(value)))
+    }
+}
+
+impl<E: GuestEnum> Pointer<RawEnum<E>> {
+    // Read and decode enum field
+    pub fn get(&self, memory: &Memory) -> Result<E, SimError> { Ok(self.view(memory)?.get()) }
+
+    // Write enum field
+    pub fn set(&self, memory: &mut Memory, value: E) -> Result<(), SimError> {
+        self.write(memory, Box::new(RawEnum::new(value)))
+    }
+}
+
+// Lets guest types take a pointer as their receiver
+impl<T> ops::Receiver for Pointer<T> {
+    type Target = T;
+}
+
 impl<T> ops::Add<u64> for Pointer<T> {
     type Output = Self;
 
-    fn add(self, rhs: u64) -> Self { Self { address: self.address + rhs, phantom: PhantomData } }
+    fn add(self, rhs: u64) -> Self {
+        Self { address: self.address.wrapping_add(rhs), phantom: PhantomData }
+    }
 }
 
 impl<T> ops::Sub<u64> for Pointer<T> {
     type Output = Self;
 
-    fn sub(self, rhs: u64) -> Self { Self { address: self.address - rhs, phantom: PhantomData } }
+    fn sub(self, rhs: u64) -> Self {
+        Self { address: self.address.wrapping_sub(rhs), phantom: PhantomData }
+    }
 }
 
-impl<T> ops::Deref for Pointer<T> {
-    type Target = ();
+impl<T> PartialEq for Pointer<T> {
+    fn eq(&self, other: &Self) -> bool { self.address == other.address }
+}
 
-    fn deref(&self) -> &() {
-        if self.address < ASLR_START || self.address + mem::size_of::<T>() as u64 > ASLR_END {
-            if mem::size_of::<T>() == 1 { panic!(
-                "Address 0x{:x} is outside ASLR range", self.address
-            ); }
-            else { panic!(
-                "Address range 0x{:x}-0x{:x} is outside ASLR range", self.address,
-                self.address + mem::size_of::<T>() as u64
-            ); }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        print!("Attempted to dereference 0x{:x}, proceed? (Y/n) ", self.address);
-        stdout().flush().unwrap();
-        let mut proceed = String::new();
-        stdin().read_line(&mut proceed).unwrap();
+    const BASE: u64 = ASLR_START + 0x1000;
 
-        if let Some(choice) = proceed.chars().next() {
-            if let Some('n') = choice.to_lowercase().next() { panic!(
-                "Invalid address 0x{:x} dereferenced", self.address
-            ); }
-        }
+    fn regions(memory: &Memory) -> Vec<(u64, Vec<u8>)> {
+        memory.regions().map(|(address, data)| (address, data.to_vec())).collect()
+    }
 
-        &()
+    #[test]
+    fn write_inside_block_keeps_one_region() {
+        let mut memory = Memory::init(vec![(BASE, vec![1; 8])]);
+        memory.write_bytes(BASE + 2, &[2, 3]);
+        assert_eq!(regions(&memory), [(BASE, vec![1, 1, 2, 3, 1, 1, 1, 1])]);
     }
-}
 
-impl<T> PartialEq for Pointer<T> {
-    fn eq(&self, other: &Self) -> bool { self.address == other.address }
+    #[test]
+    fn write_after_block_extends_it() {
+        let mut memory = Memory::init(vec![(BASE, vec![1; 4])]);
+        memory.write_bytes(BASE + 4, &[2; 4]);
+        assert_eq!(regions(&memory), [(BASE, vec![1, 1, 1, 1, 2, 2, 2, 2])]);
+    }
+
+    #[test]
+    fn write_before_block_merges_with_it() {
+        let mut memory = Memory::init(vec![(BASE + 4, vec![1; 4])]);
+        memory.write_bytes(BASE, &[2; 4]);
+        assert_eq!(regions(&memory), [(BASE, vec![2, 2, 2, 2, 1, 1, 1, 1])]);
+    }
+
+    #[test]
+    fn write_overlapping_block_ends_keeps_the_rest() {
+        let mut memory = Memory::init(vec![(BASE + 4, vec![1; 4])]);
+        memory.write_bytes(BASE + 2, &[2; 4]);
+        memory.write_bytes(BASE + 7, &[3; 3]);
+        assert_eq!(regions(&memory), [(BASE + 2, vec![2, 2, 2, 2, 1, 3, 3, 3])]);
+    }
+
+    #[test]
+    fn write_spanning_blocks_merges_them() {
+        let mut memory = Memory::init(vec![
+            (BASE, vec![1; 2]), (BASE + 4, vec![2; 2]), (BASE + 8, vec![3; 4]),
+        ]);
+        assert_eq!(regions(&memory).len(), 3);
+
+        memory.write_bytes(BASE + 1, &[4; 8]);
+        assert_eq!(regions(&memory), [(BASE, vec![1, 4, 4, 4, 4, 4, 4, 4, 4, 3, 3, 3])]);
+    }
+
+    #[test]
+    fn write_past_gap_keeps_blocks_apart() {
+        let mut memory = Memory::init(vec![(BASE, vec![1; 4])]);
+        memory.write_bytes(BASE + 5, &[2; 3]);
+        assert_eq!(regions(&memory), [(BASE, vec![1; 4]), (BASE + 5, vec![2; 3])]);
+        assert_eq!(memory.byte(BASE + 4), None);
+    }
+
+    #[test]
+    fn view_checks_alignment() {
+        let memory = Memory::init(vec![(BASE, vec![0; 16])]);
+        assert!(memory.view::<u32>(BASE + 4).is_ok());
+        assert!(matches!(
+            memory.view::<u32>(BASE + 2),
+            Err(SimError::Misaligned { address, .. }) if address == BASE + 2
+        ));
+        assert!(memory.view::<u8>(BASE + 3).is_ok());
+    }
+
+    #[test]
+    fn view_of_uninitialized_memory_fails() {
+        let memory = Memory::init(vec![(BASE, vec![0; 4]), (BASE + 8, vec![0; 4])]);
+        assert!(memory.view::<u32>(BASE).is_ok());
+
+        // Both inside the gap between two blocks and across it
+        assert!(matches!(
+            memory.view::<u32>(BASE + 4),
+            Err(SimError::UninitializedRead { range, .. }) if range == (BASE + 4..BASE + 8)
+        ));
+        assert!(matches!(
+            memory.view::<u64>(BASE),
+            Err(SimError::UninitializedRead { range, .. }) if range == (BASE..BASE + 8)
+        ));
+        assert!(matches!(
+            memory.view::<u32>(BASE - 4), Err(SimError::UninitializedRead { .. })
+        ));
+    }
+
+    #[test]
+    fn view_outside_aslr_range_fails() {
+        let memory = Memory::init(vec![]);
+        assert!(matches!(memory.view::<u32>(0x4), Err(SimError::OutOfAslrRange { .. })));
+        assert!(matches!(memory.view::<u32>(u64::MAX - 3), Err(SimError::OutOfAslrRange { .. })));
+    }
+
+    #[test]
+    fn watchpoint_stops_without_recording_writes() {
+        for record_writes in [false, true] {
+            let mut memory = Memory::init(vec![(BASE, vec![0; 8])]);
+            let watchpoint = Watchpoint { range: BASE + 4..BASE + 8, action: WatchAction::Stop };
+            memory.set_tracer(Tracer::new(vec![watchpoint], record_writes));
+
+            assert!(memory.write(BASE, Box::new(1u32)).is_ok());
+            assert!(matches!(
+                memory.write(BASE + 4, Box::new(2u32)),
+                Err(SimError::WatchpointHit { address, .. }) if address == BASE + 4
+            ));
+            let writes = memory.tracer().unwrap().writes.len();
+            assert_eq!(writes, if record_writes { 2 } else { 0 });
+        }
+    }
 }
@@ -0,0 +1,69 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Platform {
+    Switch,
+    WiiU,
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Switch => write!(f, "Switch"),
+            Self::WiiU => write!(f, "Wii U"),
+        }
+    }
+}
+
+// Guest platform the structs are laid out for, picked at build time. Switch is the default,
+// building with the wiiu feature targets the Wii U instead. Guest structs are #[repr(C)] and
+// viewed in place, so pointer width and alignment are part of their types; choosing at run
+// time would make every guest type generic over the platform. Dumps for the other platform
+// are rejected with the build they need instead.
+
+// Command building the simulator for a platform
+pub fn build_command(platform: Platform) -> &'static str {
+    match platform {
+        Platform::Switch => "cargo build",
+        Platform::WiiU => "cargo build --features wiiu",
+    }
+}
+
+#[cfg(not(feature = "wiiu"))]
+mod layout {
+    use super::Platform;
+
+    pub const PLATFORM: Platform = Platform::Switch;
+
+    // Guest pointer as stored in memory
+    pub type Address = u64;
+    pub const BIG_ENDIAN: bool = false;
+
+    // Range guest pointers may point into
+    pub const ASLR_START: u64 = 0x8000000;
+    pub const ASLR_END: u64 = 0x8000000000;
+
+    // Heap base to build PMDM at when there is no dump
+    pub const SYNTHETIC_HEAP_BASE: u64 = 0x3000000000;
+}
+
+#[cfg(feature = "wiiu")]
+mod layout {
+    use super::Platform;
+
+    pub const PLATFORM: Platform = Platform::WiiU;
+
+    // Guest pointer as stored in memory
+    pub type Address = u32;
+    pub const BIG_ENDIAN: bool = true;
+
+    // Range guest pointers may point into. The Wii U has no ASLR, so this spans the code
+    // area through the end of MEM2.
+    pub const ASLR_START: u64 = 0x1000000;
+    pub const ASLR_END: u64 = 0x50000000;
+
+    // Heap base to build PMDM at when there is no dump
+    pub const SYNTHETIC_HEAP_BASE: u64 = 0x10000000;
+}
+
+pub use layout::*;
@@ -1,8 +1,11 @@
 use std::mem;
 
+use crate::error::*;
 use crate::mem::*;
+use crate::symbol::*;
 use crate::traits::*;
 use crate::types::*;
+use crate::version::*;
 
 const MASTER_SWORD: &str = "Weapon_Sword_070";
 const REPEATABLE_KEY_ITEMS: [&str; 10] = [
@@ -18,317 +21,1046 @@ const REPEATABLE_KEY_ITEMS: [&str; 10] = [
     "Obj_KorokNuts",
 ];
 
+// Inventory order of actors whose sort keys can't be told from their names
+const ARROW_ORDER: [&str; 6] =
+    ["NormalArrow", "FireArrow", "IceArrow", "ElectricArrow", "BombArrow_A", "AncientArrow"];
+const KEY_ITEM_ORDER: [&str; 15] = [
+    "PlayerStole2", "Obj_DungeonClearSeal", "Obj_KorokNuts",
+    "Obj_HeroSoul_Zora", "Obj_HeroSoul_Rito", "Obj_HeroSoul_Goron", "Obj_HeroSoul_Gerudo",
+    "Obj_DLC_HeroSoul_Zora", "Obj_DLC_HeroSoul_Rito", "Obj_DLC_HeroSoul_Goron",
+    "Obj_DLC_HeroSoul_Gerudo", "Obj_DLC_HeroSeal_Zora", "Obj_DLC_HeroSeal_Rito",
+    "Obj_DLC_HeroSeal_Goron", "Obj_DLC_HeroSeal_Gerudo",
+];
+const MATERIAL_ORDER: [&str; 153] = [
+    "Item_Fruit_A", "Item_Fruit_G", "Item_Fruit_B", "Item_Fruit_D", "Item_Fruit_F", "Item_Fruit_I",
+    "Item_Fruit_C", "Item_Fruit_E", "Item_Fruit_H", "Item_Mushroom_N", "Item_Mushroom_F",
+    "Item_Mushroom_O", "Item_Mushroom_E", "Item_Mushroom_A", "Item_Mushroom_B", "Item_Mushroom_C",
+    "Item_Mushroom_H", "Item_Mushroom_D", "Item_Mushroom_L", "Item_Mushroom_M", "Item_Mushroom_J",
+    "Item_PlantGet_C", "Item_PlantGet_B", "Item_PlantGet_Q", "Item_PlantGet_A", "Item_PlantGet_M",
+    "Item_Fruit_J", "Item_PlantGet_E", "Item_PlantGet_F", "Item_PlantGet_L", "Item_PlantGet_O",
+    "Item_PlantGet_G", "Item_PlantGet_H", "Item_PlantGet_I", "Item_PlantGet_J", "Item_Meat_11",
+    "Item_Meat_12", "Item_Meat_02", "Item_Meat_07", "Item_Meat_01", "Item_Meat_06", "BeeHome",
+    "Item_Material_03", "Item_Material_04", "Item_Material_07", "Item_Material_05", "Item_Fruit_K",
+    "Item_Fruit_L", "Item_Material_01", "Item_Material_06", "Item_Material_02", "Item_Ore_H",
+    "Item_Material_08", "Item_Ore_J", "Item_Enemy_38", "Item_Enemy_39", "Item_Enemy_47",
+    "Item_Enemy_48", "Item_Enemy_49", "Item_Enemy_50", "Item_Enemy_51", "Item_Enemy_52",
+    "Item_Enemy_53", "Item_Enemy_54", "Item_Enemy_55", "Item_Enemy_56", "Item_FishGet_I",
+    "Item_FishGet_K", "Item_FishGet_B", "Item_FishGet_A", "Item_FishGet_L", "Item_FishGet_C",
+    "Item_FishGet_J", "Item_FishGet_D", "Item_FishGet_X", "Item_FishGet_E", "Item_FishGet_H",
+    "Item_FishGet_Z", "Item_FishGet_F", "Item_FishGet_G", "Item_FishGet_M", "Animal_Insect_K",
+    "Item_InsectGet_K", "Animal_Insect_O", "Item_InsectGet_O", "Animal_Insect_Z",
+    "Item_InsectGet_Z", "Animal_Insect_F", "Animal_Insect_N", "Animal_Insect_Q", "Animal_Insect_R",
+    "Animal_Insect_AB", "Animal_Insect_C", "Animal_Insect_T", "Animal_Insect_I", "Animal_Insect_H",
+    "Animal_Insect_G", "Animal_Insect_P", "Animal_Insect_AA", "Animal_Insect_E", "Animal_Insect_A",
+    "Animal_Insect_B", "Animal_Insect_S", "Animal_Insect_M", "Animal_Insect_X", "Item_Ore_I",
+    "Item_Ore_F", "Item_Ore_E", "Item_Ore_G", "Item_Ore_D", "Item_Ore_B", "Item_Ore_C",
+    "Item_Ore_A", "Item_Enemy_00", "Item_Enemy_01", "Item_Enemy_02", "Item_Enemy_06",
+    "Item_Enemy_07", "Item_Enemy_08", "Item_Enemy_03", "Item_Enemy_04", "Item_Enemy_05",
+    "Item_Enemy_42", "Item_Enemy_41", "Item_Enemy_43", "Item_Enemy_12", "Item_Enemy_13",
+    "Item_Enemy_14", "Item_Enemy_40", "Item_Enemy_17", "Item_Enemy_15", "Item_Enemy_16",
+    "Item_Enemy_18", "Item_Enemy_46", "Item_Enemy_44", "Item_Enemy_45", "Item_Enemy_19",
+    "Item_Enemy_20", "Item_Enemy_21", "Item_Enemy_57", "Item_Enemy_24", "Item_Enemy_25",
+    "Item_Enemy_32", "Item_Enemy_33", "Item_Enemy_34", "Item_Enemy_27", "Item_Enemy_28",
+    "Item_Enemy_26", "Item_Enemy_29", "Item_Enemy_30", "Item_Enemy_31", "FireWood",
+    "FireWoodFromBundle",
+];
+
+const MAX_STACK_VALUE: i32 = 999;
+pub const NUM_ITEMS_PER_TAB: i32 = 20;
+
+// Whether picking up the item adds to an existing slot
+fn can_stack(name: &str, item_type: PouchItemType) -> bool {
+    match item_type {
+        PouchItemType::Arrow | PouchItemType::Material => true,
+        PouchItemType::Food => !name.starts_with("Item_Cook"),
+        PouchItemType::KeyItem => REPEATABLE_KEY_ITEMS.contains(&name),
+        _ => false,
+    }
+}
+
+// Approximate ItemUse from the actor name, as actor info is unavailable
+fn get_item_use(name: &str, item_type: PouchItemType) -> ItemUse {
+    match item_type {
+        PouchItemType::Sword if name.starts_with("Weapon_Lsword") => ItemUse::WeaponLargeSword,
+        PouchItemType::Sword if name.starts_with("Weapon_Spear") => ItemUse::WeaponSpear,
+        PouchItemType::Sword => ItemUse::WeaponSmallSword,
+        PouchItemType::Bow => ItemUse::WeaponBow,
+        PouchItemType::Shield => ItemUse::WeaponShield,
+        PouchItemType::ArmorHead => ItemUse::ArmorHead,
+        PouchItemType::ArmorUpper => ItemUse::ArmorUpper,
+        PouchItemType::ArmorLower => ItemUse::ArmorLower,
+        PouchItemType::Arrow | PouchItemType::Material => ItemUse::Item,
+        PouchItemType::Food => ItemUse::CureItem,
+        PouchItemType::KeyItem => ItemUse::ImportantItem,
+        PouchItemType::Invalid | PouchItemType::Unknown(_) => ItemUse::Invalid,
+    }
+}
+
+// Approximate the actor's sort key, as actor info is unavailable: its position in the
+// inventory order if known, else the number in its name, e.g. 31 for Weapon_Sword_031 or 5 for
+// Armor_005_Head. Orderings known to differ from the game's:
+// - weapons, bows and shields whose numbers don't follow the game's order, like the Master
+//   Sword, champion and DLC weapons
+// - armor sets whose numbers don't follow the game's set order
+// - food other than cooked meals, like roasted and frozen items, which go by their numbers
+// - arrows, materials and key items missing from the order tables, which go after listed ones
+//   in the order they were added
+fn get_sort_key(name: &str, item_type: PouchItemType) -> Option<i32> {
+    let order: &[&str] = match item_type {
+        PouchItemType::Arrow => &ARROW_ORDER,
+        PouchItemType::Material => &MATERIAL_ORDER,
+        PouchItemType::KeyItem => &KEY_ITEM_ORDER,
+        _ => &[],
+    };
+    if let Some(index) = order.iter().position(|&actor| actor == name) {
+        return Some(index as i32);
+    }
+    name.split('_').find_map(|part| part.parse().ok())
+}
+
+// Order actors by sort key, putting unknown actors after known ones in their current order
+fn compare_sort_keys(lhs: &PouchItem, rhs: &PouchItem) -> i32 {
+    let lhs_key = get_sort_key(&lhs.name.to_string(), lhs.item_type.get());
+    let rhs_key = get_sort_key(&rhs.name.to_string(), rhs.item_type.get());
+    match (lhs_key, rhs_key) {
+        (Some(lhs_key), Some(rhs_key)) => lhs_key.cmp(&rhs_key) as i32,
+        (Some(_), None) => -1,
+        (None, Some(_)) => 1,
+        (None, None) => 0,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ItemList {
+    List1,
+    List2,
+}
+
+#[derive(Clone, Debug)]
+pub struct CycleNode {
+    pub address: u64,
+    // Name of the item containing the node, if it is readable
+    pub name: Option<String>,
+}
+
+// Result of simulating an action
+#[derive(Debug)]
+pub enum Outcome<T = ()> {
+    Done(T),
+    Frozen { list: ItemList, cycle_nodes: Vec<CycleNode> },
+}
+
+impl<T> Outcome<T> {
+    // Turn a game freeze into an outcome, passing other errors on
+    pub fn catch(result: Result<T, SimError>) -> Result<Self, SimError> {
+        match result {
+            Ok(value) => Ok(Self::Done(value)),
+            Err(SimError::GameFreeze { list, cycle_nodes }) => {
+                Ok(Self::Frozen { list, cycle_nodes })
+            },
+            Err(err) => Err(err),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct PauseMenuDataMgr {
-    pub vptr: Pointer<Pointer>,
-    pub singleton_disposer_buf: [u32; 0x8],
+    pub vptr: Guest<Pointer<Pointer>>,
+    pub singleton_disposer_buf: [Guest<Pointer>; 0x4],
     pub crit_section: CriticalSection,
     pub item_lists: Lists,
-    pub list_heads: SafeArray<Pointer<Pointer<PouchItem>>, NUM_POUCH_CATEGORIES>,
-    pub tabs: SafeArray<Pointer<PouchItem>, NUM_TAB_MAX>,
-    pub tabs_type: SafeArray<PouchItemType, NUM_TAB_MAX>,
-    pub last_added_item: Pointer<PouchItem>,
-    pub last_added_item_tab: i32,
-    pub last_added_item_slot: i32,
-    pub num_tabs: i32,
+    pub list_heads: SafeArray<Guest<Pointer<Guest<Pointer<PouchItem>>>>, NUM_POUCH_CATEGORIES>,
+    pub tabs: SafeArray<Guest<Pointer<PouchItem>>, NUM_TAB_MAX>,
+    pub tabs_type: SafeArray<RawEnum<PouchItemType>, NUM_TAB_MAX>,
+    pub last_added_item: Guest<Pointer<PouchItem>>,
+    pub last_added_item_tab: Guest<i32>,
+    pub last_added_item_slot: Guest<i32>,
+    pub num_tabs: Guest<i32>,
     pub grabbed_items: SafeArray<GrabbedItemInfo, NUM_GRABBABLE_ITEMS>,
-    item_444f0: Pointer<PouchItem>,
-    _444f8: i32,
-    _444fc: i32,
-    _44500: i32,
-    _44504: u32,
-    _44508: u32,
-    _4450c: u32,
-    _44510: u32,
-    _44514: u32,
-    pub rito_soul_item: Pointer<PouchItem>,
-    pub goron_soul_item: Pointer<PouchItem>,
-    pub zora_soul_item: Pointer<PouchItem>,
-    pub gerudo_soul_item: Pointer<PouchItem>,
-    pub can_see_health_bar: bool,
+    item_444f0: Guest<Pointer<PouchItem>>,
+    _444f8: Guest<i32>,
+    _444fc: Guest<i32>,
+    _44500: Guest<i32>,
+    _44504: Guest<u32>,
+    _44508: Guest<u32>,
+    _4450c: Guest<u32>,
+    _44510: Guest<u32>,
+    _44514: Guest<u32>,
+    pub rito_soul_item: Guest<Pointer<PouchItem>>,
+    pub goron_soul_item: Guest<Pointer<PouchItem>>,
+    pub zora_soul_item: Guest<Pointer<PouchItem>>,
+    pub gerudo_soul_item: Guest<Pointer<PouchItem>>,
+    pub can_see_health_bar: Guest<bool>,
     pub newly_added_item: PouchItem,
-    pub is_pouch_for_quest: bool,
-    pub equipped_weapons: SafeArray<Pointer<PouchItem>, 4>,
-    pub category_to_sort: PouchCategory,
+    pub is_pouch_for_quest: Guest<bool>,
+    pub equipped_weapons: SafeArray<Guest<Pointer<PouchItem>>, 4>,
+    pub category_to_sort: RawEnum<PouchCategory>,
 }
 
+symbolize_fields!(PauseMenuDataMgr {
+    vptr, singleton_disposer_buf, crit_section, item_lists, list_heads, tabs, tabs_type,
+    last_added_item, last_added_item_tab, last_added_item_slot, num_tabs, grabbed_items,
+    item_444f0, _444f8, _444fc, _44500, _44504, _44508, _4450c, _44510, _44514,
+    rito_soul_item, goron_soul_item, zora_soul_item, gerudo_soul_item, can_see_health_bar,
+    newly_added_item, is_pouch_for_quest, equipped_weapons, category_to_sort,
+});
+
 impl PauseMenuDataMgr {
-    fn get_item_head(&self, memory: &Memory, category: PouchCategory) -> Pointer<PouchItem> {
-        let p_head = self.list_heads.buffer[category as u32 as usize];
-        if p_head != Pointer::NULLPTR { *p_head.to_ne().read(memory).unwrap() } else {
-            Pointer::NULLPTR
-        }
+    pub fn list1(self: Pointer<Self>) -> Pointer<OffsetList<PouchItem>> {
+        field!(self, item_lists.list1)
     }
 
-    fn reset_item(&mut self, memory: &mut Memory, this: Pointer<Self>) {
-        (this.cast() + mem::offset_of!(Self, newly_added_item.item_type) as u64).write(
-            memory, Box::new((PouchItemType::default() as i32).to_le())
-        ).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, newly_added_item.item_use) as u64).write(
-            memory, Box::new((ItemUse::default() as i32).to_le())
-        ).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, newly_added_item.value) as u64).write(
-            memory, Box::new(i32::default())
-        ).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, newly_added_item.equipped) as u64).write(
-            memory, Box::new(bool::default())
-        ).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, newly_added_item.in_inventory) as u64).write(
-            memory, Box::new(bool::default())
-        ).unwrap();
-        self.update(memory, &this);
-        self.newly_added_item.name.clear(memory);
-        (this.cast() + mem::offset_of!(Self, newly_added_item.data.cook.effect_id) as u64).write(
-            memory, Box::new((CookEffectId::default() as i32 as f32).to_le_bytes())
-        ).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, newly_added_item.data.cook.effect_level) as u64).write(
-            memory, Box::new(f32::default().to_le_bytes())
-        ).unwrap();
-        self.update(memory, &this);
+    pub fn list2(self: Pointer<Self>) -> Pointer<OffsetList<PouchItem>> {
+        field!(self, item_lists.list2)
     }
 
-    fn reset_item_and_pointers(&mut self, memory: &mut Memory, this: Pointer<Self>) {
-        (this.cast() + mem::offset_of!(Self, last_added_item) as u64).write(memory, Box::new(
-            Pointer::<PouchItem>::NULLPTR
-        )).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, item_444f0) as u64).write(memory, Box::new(
-            Pointer::<PouchItem>::NULLPTR
-        )).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, _444f8) as u64).write(memory, Box::new(
-            -1i32.to_le()
-        )).unwrap();
-        self.update(memory, &this);
-        self.reset_item(memory, this);
+    // Index of item in item_lists.buffer, if it is one of the buffer's items
+    pub fn item_index(self: Pointer<Self>, item_ptr: Pointer<PouchItem>) -> Option<usize> {
+        let buffer = field!(self, item_lists.buffer.buffer[0]);
+        let offset = item_ptr.address.checked_sub(buffer.address)? as usize;
+        let index = offset / mem::size_of::<PouchItem>();
+        (offset.is_multiple_of(mem::size_of::<PouchItem>()) && index < NUM_POUCH_ITEMS_MAX as usize)
+            .then_some(index)
     }
 
-    fn update_inventory_info(&self, memory: &Memory, this: Pointer<Self>) {}
+    fn get_item_head(
+        self: Pointer<Self>, memory: &Memory, category: PouchCategory
+    ) -> Result<Pointer<PouchItem>, SimError> {
+        let p_head = self.view(memory)?.list_heads.buffer[category.raw() as usize].get();
+        Ok(if p_head != Pointer::NULLPTR { p_head.get(memory)? } else { Pointer::NULLPTR })
+    }
 
-    fn update_list_heads(&mut self, memory: &mut Memory, this: Pointer<Self>) {}
+    fn reset_item(self: Pointer<Self>, memory: &mut Memory) -> Result<(), SimError> {
+        let item = field!(self, newly_added_item);
+        field!(item, item_type).set(memory, PouchItemType::default())?;
+        field!(item, item_use).set(memory, ItemUse::default())?;
+        field!(item, value).set(memory, i32::default())?;
+        field!(item, equipped).set(memory, bool::default())?;
+        field!(item, in_inventory).set(memory, bool::default())?;
+        field!(item, name).clear(memory)?;
+        field!(item, data.cook.effect_id).set(
+            memory, CookEffectId::default().raw() as f32
+        )?;
+        field!(item, data.cook.effect_level).set(memory, f32::default())?;
+        Ok(())
+    }
 
-    fn update_after_adding_item(&mut self, memory: &mut Memory, this: Pointer<Self>) {
-        if self.item_lists.list1.count == 0 { return; }
+    fn reset_item_and_pointers(
+        self: Pointer<Self>, memory: &mut Memory
+    ) -> Result<(), SimError> {
+        field!(self, last_added_item).set(memory, Pointer::NULLPTR)?;
+        field!(self, item_444f0).set(memory, Pointer::NULLPTR)?;
+        field!(self, _444f8).set(memory, -1)?;
+        self.reset_item(memory)?;
+        Ok(())
+    }
 
-        (this.cast() + mem::offset_of!(Self, category_to_sort) as u64).write(
-            memory, Box::new((PouchCategory::Invalid as i32).to_le())
-        ).unwrap();
-        self.update(memory, &this);
-        let list1 = this.cast::<OffsetList<PouchItem>>() + mem::offset_of!(
-            Self, item_lists.list1
-        ) as u64;
-        list1.read(memory).unwrap().sort(memory, list1, Self::sort_predicate);
+    // Rebuild tabs from list1, walking it from nth(0) through next like the game. list1.count
+    // only decides whether there are any tabs, items linked past it still get tabs.
+    fn update_inventory_info(
+        self: Pointer<Self>, memory: &mut Memory
+    ) -> Result<(), SimError> {
+        field!(self, tabs).write(memory, Box::new(SafeArray::default()))?;
+        field!(self, tabs_type).write(memory, Box::new(SafeArray {
+            buffer: [RawEnum::new(PouchItemType::Invalid); NUM_TAB_MAX as usize]
+        }))?;
+
+        self.traverse_list1(memory)?;
+        let mut item_ptr = self.list1().view(memory)?.nth(memory, 0)?;
+        let mut num_tabs = 0;
+        let mut num_items_in_tab = 0;
+        let mut prev_category = PouchCategory::Invalid;
+
+        while item_ptr != Pointer::NULLPTR {
+            let item_type = item_ptr.view(memory)?.item_type.get();
+            let category = item_type.category();
+
+            // Start new tab on category change or when the current tab is full
+            if num_tabs == 0 || category != prev_category || num_items_in_tab >= NUM_ITEMS_PER_TAB {
+                if num_tabs >= NUM_TAB_MAX { break; }
+
+                let tab = num_tabs as usize;
+                field!(self, tabs.buffer[tab]).set(memory, item_ptr)?;
+                field!(self, tabs_type.buffer[tab]).set(memory, item_type)?;
+
+                num_tabs += 1;
+                num_items_in_tab = 0;
+            }
+
+            num_items_in_tab += 1;
+            prev_category = category;
+            item_ptr = self.list1().next(memory, item_ptr)?;
+        }
+
+        field!(self, num_tabs).set(memory, num_tabs)?;
+        Ok(())
+    }
+
+    // Point each category's list head at its first tab
+    fn update_list_heads(
+        self: Pointer<Self>, memory: &mut Memory
+    ) -> Result<(), SimError> {
+        let mut list_heads = SafeArray::<
+            Guest<Pointer<Guest<Pointer<PouchItem>>>>, NUM_POUCH_CATEGORIES
+        >::default();
 
-        self.update_inventory_info(memory, this);
-        self.update_list_heads(memory, this);
-        self.sync(memory, this);
+        let pmdm = self.view(memory)?;
+        for i in 0..pmdm.num_tabs.get().clamp(0, NUM_TAB_MAX) as usize {
+            let category = pmdm.tabs_type.buffer[i].get().category();
+            if category == PouchCategory::Invalid { continue; }
+
+            let list_head = &mut list_heads.buffer[category.raw() as usize];
+            if list_head.get() == Pointer::NULLPTR {
+                list_head.set(field!(self, tabs.buffer[i]));
+            }
+        }
+
+        field!(self, list_heads).write(memory, Box::new(list_heads))?;
+        Ok(())
     }
 
-    fn sort_predicate(memory: &Memory, lhs: Pointer<PouchItem>, rhs: Pointer<PouchItem>) -> i32 {
-        0
+    fn update_after_adding_item(
+        self: Pointer<Self>, memory: &mut Memory
+    ) -> Result<(), SimError> {
+        if self.list1().view(memory)?.count.get() == 0 { return Ok(()); }
+
+        field!(self, category_to_sort).set(memory, PouchCategory::Invalid)?;
+        self.list1().sort(memory, |memory, lhs, rhs| self.sort_predicate(memory, lhs, rhs))?;
+
+        self.update_inventory_info(memory)?;
+        self.update_list_heads(memory)?;
+        self.sync(memory)?;
+        Ok(())
     }
 
-    // Check for loops in list1
-    fn traverse_list1(&self, memory: &Memory, this: Pointer<Self>) {
-        let list1 = self.item_lists.list1;
-        if list1.count == 0 { return; }
+    // Order items by category, type, then per-type rules
+    fn sort_predicate(
+        self: Pointer<Self>, memory: &Memory, lhs: Pointer<PouchItem>, rhs: Pointer<PouchItem>
+    ) -> Result<i32, SimError> {
+        if lhs == Pointer::NULLPTR || rhs == Pointer::NULLPTR { return Ok(0); }
+        let lhs = lhs.view(memory)?;
+        let rhs = rhs.view(memory)?;
 
-        // Traverse list1 until mStartEnd reached
-        let mut node = list1.start_end.next.to_ne();
-        let mut visited_nodes = vec![node];
+        // Only compare items in the category being sorted, if any
+        let category_to_sort = self.view(memory)?.category_to_sort.get().raw();
+        let lhs_category = lhs.item_type.get().category().raw();
+        let rhs_category = rhs.item_type.get().category().raw();
+        if category_to_sort != PouchCategory::Invalid.raw() && (
+            lhs_category != category_to_sort || rhs_category != category_to_sort
+        ) { return Ok(0); }
 
-        while node != (this.cast() + mem::offset_of!(Self, item_lists.list1.start_end) as u64) {
-            // Prevent cyclic nodes from hanging
-            if visited_nodes.contains(&node) { panic!("Game has frozen due to cyclic nodes"); }
-            visited_nodes.push(node);
-            node = node.read(memory).unwrap().next.to_ne();
+        if lhs_category != rhs_category { return Ok(lhs_category.cmp(&rhs_category) as i32); }
+
+        // Armor is grouped by set, then by slot
+        let lhs_type = lhs.item_type.get();
+        let rhs_type = rhs.item_type.get();
+        if lhs_category == PouchCategory::Armor.raw() {
+            let cmp = compare_sort_keys(lhs, rhs);
+            return Ok(if cmp != 0 { cmp } else { lhs_type.raw().cmp(&rhs_type.raw()) as i32 });
         }
+
+        if lhs_type != rhs_type { return Ok(lhs_type.raw().cmp(&rhs_type.raw()) as i32); }
+
+        Ok(match lhs_type {
+            PouchItemType::Sword | PouchItemType::Bow | PouchItemType::Shield => {
+                let lhs_use = lhs.item_use.get().raw();
+                let rhs_use = rhs.item_use.get().raw();
+                if lhs_use != rhs_use { lhs_use.cmp(&rhs_use) as i32 } else {
+                    compare_sort_keys(lhs, rhs)
+                }
+            },
+            PouchItemType::Food => {
+                // Cooked meals come first and keep the order they were made in. The game
+                // may order them by more, this has not been checked.
+                let lhs_cooked = lhs.name.to_string().starts_with("Item_Cook");
+                let rhs_cooked = rhs.name.to_string().starts_with("Item_Cook");
+                if lhs_cooked || rhs_cooked { rhs_cooked.cmp(&lhs_cooked) as i32 } else {
+                    compare_sort_keys(lhs, rhs)
+                }
+            },
+            _ => compare_sort_keys(lhs, rhs),
+        })
+    }
+
+    // Take item from list2 and add it to the end of list1
+    fn add_to_pouch(
+        self: Pointer<Self>, memory: &mut Memory, name: &str, item_type: PouchItemType,
+        value: i32, equipped: bool
+    ) -> Result<Pointer<PouchItem>, SimError> {
+        // Take free item from list2
+        let item_ptr = self.list2().pop_front(memory)?;
+        if item_ptr == Pointer::NULLPTR { return Ok(item_ptr); }
+
+        // Construct item
+        item_ptr.ctor(memory)?;
+
+        // Add item to list1
+        self.list1().push_back(memory, item_ptr)?;
+
+        // Set item data
+        field!(item_ptr, item_type).set(memory, item_type)?;
+        field!(item_ptr, item_use).set(memory, get_item_use(name, item_type))?;
+        field!(item_ptr, value).set(memory, value)?;
+        field!(item_ptr, equipped).set(
+            memory, equipped && item_type.raw() <= PouchItemType::ArmorLower.raw()
+        )?;
+        field!(item_ptr, in_inventory).set(memory, true)?;
+        field!(item_ptr, name).copy_str(memory, name)?;
+
+        field!(self, last_added_item).set(memory, item_ptr)?;
+        Ok(item_ptr)
+    }
+
+    // Check for loops in list1
+    fn traverse_list1(self: Pointer<Self>, memory: &Memory) -> Result<(), SimError> {
+        self.traverse_list(memory, self.list1(), ItemList::List1)
     }
 
     // Check for loops in list2
-    fn traverse_list2(&self, memory: &Memory, this: Pointer<Self>) {
-        let list2 = self.item_lists.list2;
-        if list2.count == 0 { return; }
+    fn traverse_list2(self: Pointer<Self>, memory: &Memory) -> Result<(), SimError> {
+        self.traverse_list(memory, self.list2(), ItemList::List2)
+    }
+
+    fn traverse_list(
+        self: Pointer<Self>, memory: &Memory, list_ptr: Pointer<OffsetList<PouchItem>>,
+        list: ItemList
+    ) -> Result<(), SimError> {
+        let offset_list = list_ptr.view(memory)?;
+        if offset_list.count.get() == 0 { return Ok(()); }
 
-        // Traverse list1 until mStartEnd reached
-        let mut node = list2.start_end.next.to_ne();
-        let mut visited_nodes = vec![node];
+        // Traverse list until mStartEnd reached
+        let start_end = field!(list_ptr, start_end);
+        let mut node = offset_list.start_end.next.get();
+        let mut visited_nodes: Vec<Pointer<ListNode>> = vec![];
 
-        while node != (this.cast() + mem::offset_of!(Self, item_lists.list2.start_end) as u64) {
+        while node != start_end {
             // Prevent cyclic nodes from hanging
-            if visited_nodes.contains(&node) { panic!("Game has frozen due to cyclic nodes"); }
+            if let Some(i) = visited_nodes.iter().position(|&visited| visited == node) {
+                let cycle_nodes = visited_nodes[i..].iter().map(|&node| CycleNode {
+                    address: node.address,
+                    name: offset_list.list_node_to_obj(node).view(memory).ok().map(
+                        |item| item.name.to_string()
+                    ),
+                }).collect();
+                return Err(SimError::GameFreeze { list, cycle_nodes });
+            }
             visited_nodes.push(node);
-            node = node.read(memory).unwrap().next.to_ne();
+            node = node.view(memory)?.next.get();
         }
+        Ok(())
     }
 
     // Pick up item
     pub fn get(
-        &mut self, memory: &mut Memory, this: Pointer<Self>, name: &str, item_type: PouchItemType,
+        self: Pointer<Self>, memory: &mut Memory, name: &str, item_type: PouchItemType,
+        value: i32, modifier: Pointer<WeaponModifierInfo>
+    ) -> Result<Outcome, SimError> {
+        Outcome::catch(self.get_impl(memory, name, item_type, value, modifier))
+    }
+
+    fn get_impl(
+        self: Pointer<Self>, memory: &mut Memory, name: &str, item_type: PouchItemType,
         value: i32, modifier: Pointer<WeaponModifierInfo>
-    ) {
+    ) -> Result<(), SimError> {
         if item_type == PouchItemType::KeyItem && !REPEATABLE_KEY_ITEMS.contains(&name) {
-            self.traverse_list1(memory, this);
-            let mut item_ptr = self.get_item_head(memory, PouchCategory::KeyItem).to_ne();
-
-            while item_ptr != Pointer::NULLPTR && i32::from_le(item_ptr.read(
-                memory
-            ).unwrap().item_type as i32) == PouchItemType::KeyItem as i32 {
-                let mut item = item_ptr.read(memory).unwrap();
-                if item.in_inventory && item.name.is_equal_str(
-                    memory, item_ptr.cast() + mem::offset_of!(PouchItem, name) as u64, name
-                ) { return; }
-                self.update(memory, &this);
-
-                item_ptr = self.item_lists.list1.next(
-                    memory, this.cast() + mem::offset_of!(Self, item_lists.list1) as u64, item_ptr
-                );
+            self.traverse_list1(memory)?;
+            let mut item_ptr = self.get_item_head(memory, PouchCategory::KeyItem)?;
+
+            while item_ptr != Pointer::NULLPTR
+                && field!(item_ptr, item_type).get(memory)? == PouchItemType::KeyItem {
+                if item_ptr.view(memory)?.in_inventory.get()
+                    && field!(item_ptr, name).is_equal_str(memory, name)? {
+                    return Ok(());
+                }
+
+                item_ptr = self.list1().next(memory, item_ptr)?;
             }
         }
         else if item_type == PouchItemType::Sword && name == MASTER_SWORD {
-            self.traverse_list1(memory, this);
-            let mut item_ptr = self.get_item_head(memory, PouchCategory::Sword).to_ne();
-
-            if item_ptr != Pointer::NULLPTR {
-                let mut item = item_ptr.read(memory).unwrap();
-
-                while i32::from_le(item.item_type as i32) == PouchItemType::Sword as i32 {
-                    if !item.in_inventory || !item.name.is_equal_str(
-                        memory, item_ptr.cast() + mem::offset_of!(PouchItem, name) as u64, name
-                    ) {
-                        item_ptr = self.item_lists.list1.next(memory, this.cast() + mem::offset_of!(
-                            Self, item_lists.list1
-                        ) as u64, item_ptr);
-                        if item_ptr == Pointer::NULLPTR { break; }
-                        item.update(memory, &item_ptr);
-                        continue;
-                    }
-
-                    (item_ptr.cast() + mem::offset_of!(PouchItem, value) as u64).write(
-                        memory, Box::new(i32::default())
-                    ).unwrap();
-                    self.update(memory, &this);
-                    item.update(memory, &item_ptr);
-                    (item_ptr.cast() + mem::offset_of!(PouchItem, equipped) as u64).write(
-                        memory, Box::new(bool::default())
-                    ).unwrap();
-                    self.update(memory, &this);
-                    item.update(memory, &item_ptr);
-
-                    (this.cast() + mem::offset_of!(Self, last_added_item) as u64).write(
-                        memory, Box::new(if i32::from_le(item.value) > 0 { item_ptr.to_le() } else {
-                            Pointer::NULLPTR
-                        })
-                    ).unwrap();
-                    self.update(memory, &this);
-                    self.reset_item(memory, this);
-                    return;
+            self.traverse_list1(memory)?;
+            let mut item_ptr = self.get_item_head(memory, PouchCategory::Sword)?;
+
+            while item_ptr != Pointer::NULLPTR
+                && field!(item_ptr, item_type).get(memory)? == PouchItemType::Sword {
+                if !item_ptr.view(memory)?.in_inventory.get()
+                    || !field!(item_ptr, name).is_equal_str(memory, name)? {
+                    item_ptr = self.list1().next(memory, item_ptr)?;
+                    continue;
                 }
+
+                field!(item_ptr, value).set(memory, i32::default())?;
+                field!(item_ptr, equipped).set(memory, bool::default())?;
+
+                let item_value = field!(item_ptr, value).get(memory)?;
+                field!(self, last_added_item).set(
+                    memory, if item_value > 0 { item_ptr } else { Pointer::NULLPTR }
+                )?;
+                self.reset_item(memory)?;
+                return Ok(());
             }
         }
 
-        if item_type == PouchItemType::Invalid { return; }
+        if item_type == PouchItemType::Invalid { return Ok(()); }
 
-        // TODO
+        // Stack onto existing item
+        if can_stack(name, item_type) {
+            self.traverse_list1(memory)?;
+            let mut item_ptr = self.get_item_head(memory, item_type.category())?;
 
-        self.update_after_adding_item(memory, this);
+            while item_ptr != Pointer::NULLPTR {
+                let item = item_ptr.view(memory)?;
+                let current_type = item.item_type.get().raw();
+                if current_type > item_type.raw() { break; }
+
+                if current_type == item_type.raw() && item.in_inventory.get()
+                    && field!(item_ptr, name).is_equal_str(memory, name)? {
+                    let item_value = field!(item_ptr, value);
+                    item_value.set(
+                        memory, item_value.get(memory)?.saturating_add(value).min(MAX_STACK_VALUE)
+                    )?;
+
+                    field!(self, last_added_item).set(memory, item_ptr)?;
+                    self.reset_item(memory)?;
+                    self.update_after_adding_item(memory)?;
+                    return Ok(());
+                }
+
+                item_ptr = self.list1().next(memory, item_ptr)?;
+            }
+        }
+
+        // Add new item
+        let item_ptr = self.add_to_pouch(memory, name, item_type, value, false)?;
+        if item_ptr == Pointer::NULLPTR {
+            self.reset_item(memory)?;
+            return Ok(());
+        }
+
+        // Set weapon modifier
+        if modifier != Pointer::NULLPTR {
+            let flags = field!(modifier, flags.bits).cast::<u32>().get(memory)?;
+            field!(item_ptr, data.weapon.modifier).set(memory, flags)?;
+            let modifier_value = field!(modifier, value).get(memory)?;
+            field!(item_ptr, data.weapon.modifier_value).set(
+                memory, modifier_value as u32
+            )?;
+        }
+
+        self.reset_item(memory)?;
+        self.update_after_adding_item(memory)?;
+        Ok(())
     }
 
     // Remove item slot while unpaused
-    pub fn remove(&mut self, memory: &mut Memory, this: Pointer<Self>, item: Pointer<PouchItem>) {
-        if self.item_444f0.to_ne() == item {
-            (this.cast() + mem::offset_of!(Self, item_444f0) as u64).write(
-                memory, Box::new(Pointer::<PouchItem>::NULLPTR)
-            ).unwrap();
-            self.update(memory, &this);
-        }
-        if self.last_added_item.to_ne() == item {
-            (this.cast() + mem::offset_of!(Self, last_added_item) as u64).write(
-                memory, Box::new(Pointer::<PouchItem>::NULLPTR)
-            ).unwrap();
-            self.update(memory, &this);
+    pub fn remove(
+        self: Pointer<Self>, memory: &mut Memory, item: Pointer<PouchItem>
+    ) -> Result<Outcome, SimError> {
+        Outcome::catch(self.remove_impl(memory, item))
+    }
+
+    fn remove_impl(
+        self: Pointer<Self>, memory: &mut Memory, item: Pointer<PouchItem>
+    ) -> Result<(), SimError> {
+        for item_ref in [field!(self, item_444f0), field!(self, last_added_item)] {
+            if item_ref.get(memory)? == item { item_ref.set(memory, Pointer::NULLPTR)?; }
         }
 
-        self.item_lists.list1.erase(
-            memory, this.cast() + mem::offset_of!(Self, item_lists.list1) as u64, item
-        );
+        self.list1().erase(memory, item)?;
 
-        self.sync(memory, this);
+        self.sync(memory)?;
+        Ok(())
     }
 
     // Remove item slot while paused
-    pub fn drop(&mut self, memory: &mut Memory, this: Pointer<Self>, item: Pointer<PouchItem>) {
-        (item.cast() + mem::offset_of!(PouchItem, in_inventory) as u64).write(
-            memory, Box::new(false)
-        ).unwrap();
-        self.update(memory, &this);
+    pub fn drop(
+        self: Pointer<Self>, memory: &mut Memory, item: Pointer<PouchItem>
+    ) -> Result<Outcome, SimError> {
+        Outcome::catch(self.drop_impl(memory, item))
+    }
+
+    fn drop_impl(
+        self: Pointer<Self>, memory: &mut Memory, item: Pointer<PouchItem>
+    ) -> Result<(), SimError> {
+        field!(item, in_inventory).set(memory, false)?;
+        Ok(())
     }
 
     // Damage or shoot item
     pub fn set_value(
-        &mut self, memory: &mut Memory, this: Pointer<Self>, item: Pointer<PouchItem>, value: i32
-    ) {
-        (item.cast() + mem::offset_of!(PouchItem, value) as u64).write(
-            memory, Box::new(value.to_le())
-        ).unwrap();
-        self.update(memory, &this);
+        self: Pointer<Self>, memory: &mut Memory, item: Pointer<PouchItem>, value: i32
+    ) -> Result<Outcome, SimError> {
+        Outcome::catch(self.set_value_impl(memory, item, value))
+    }
+
+    fn set_value_impl(
+        self: Pointer<Self>, memory: &mut Memory, item: Pointer<PouchItem>, value: i32
+    ) -> Result<(), SimError> {
+        field!(item, value).set(memory, value)?;
+        Ok(())
     }
 
     // Equip or enable item
-    pub fn equip(&mut self, memory: &mut Memory, this: Pointer<Self>, item: Pointer<PouchItem>) {
-        (item.cast() + mem::offset_of!(PouchItem, equipped) as u64).write(
-            memory, Box::new(true)
-        ).unwrap();
-        self.update(memory, &this);
-        self.sync(memory, this);
+    pub fn equip(
+        self: Pointer<Self>, memory: &mut Memory, item: Pointer<PouchItem>
+    ) -> Result<Outcome, SimError> {
+        Outcome::catch(self.equip_impl(memory, item))
+    }
+
+    fn equip_impl(
+        self: Pointer<Self>, memory: &mut Memory, item: Pointer<PouchItem>
+    ) -> Result<(), SimError> {
+        field!(item, equipped).set(memory, true)?;
+        self.sync(memory)?;
+        Ok(())
     }
 
     // Unequip or disable item
-    pub fn unequip(&mut self, memory: &mut Memory, this: Pointer<Self>, item: Pointer<PouchItem>) {
-        (item.cast() + mem::offset_of!(PouchItem, equipped) as u64).write(
-            memory, Box::new(false)
-        ).unwrap();
-        self.update(memory, &this);
-        self.sync(memory, this);
+    pub fn unequip(
+        self: Pointer<Self>, memory: &mut Memory, item: Pointer<PouchItem>
+    ) -> Result<Outcome, SimError> {
+        Outcome::catch(self.unequip_impl(memory, item))
+    }
+
+    fn unequip_impl(
+        self: Pointer<Self>, memory: &mut Memory, item: Pointer<PouchItem>
+    ) -> Result<(), SimError> {
+        field!(item, equipped).set(memory, false)?;
+        self.sync(memory)?;
+        Ok(())
     }
 
     // Open inventory
-    pub fn pause(&self, memory: &Memory, this: Pointer<Self>) { self.traverse_list1(memory, this); }
+    pub fn pause(self: Pointer<Self>, memory: &Memory) -> Result<Outcome, SimError> {
+        Outcome::catch(self.pause_impl(memory))
+    }
 
-    // Sync GameData
-    pub fn sync(&mut self, memory: &mut Memory, this: Pointer<Self>) {
-        self.update(memory, &this);
+    fn pause_impl(self: Pointer<Self>, memory: &Memory) -> Result<(), SimError> {
+        self.traverse_list1(memory)
     }
 
+    // Sync GameData, whose flags are not simulated
+    pub fn sync(self: Pointer<Self>, _memory: &mut Memory) -> Result<(), SimError> { Ok(()) }
+
     // Save file
-    pub fn save(&self, memory: &Memory, this: Pointer<Self>) -> GameData {
-        vec![]
+    pub fn save(
+        self: Pointer<Self>, memory: &Memory
+    ) -> Result<Outcome<GameData>, SimError> {
+        Outcome::catch(self.save_impl(memory))
+    }
+
+    fn save_impl(self: Pointer<Self>, memory: &Memory) -> Result<GameData, SimError> {
+        self.traverse_list1(memory)?;
+        let list1 = self.list1().view(memory)?;
+        let start_end = field!(self.list1(), start_end);
+        let mut node = list1.start_end.next.get();
+        let mut file = GameData::new();
+
+        // Walk the whole chain, including items past list1.count
+        while node != start_end && file.len() < NUM_POUCH_ITEMS_MAX as usize {
+            let item = list1.list_node_to_obj(node).view(memory)?;
+            node = item.list_node.next.get();
+            if !item.in_inventory.get() { continue; }
+
+            file.push(GameDataItem {
+                name: item.name.to_string(),
+                item_type: item.item_type.get(),
+                equipped: item.equipped.get(),
+                value: item.value.get(),
+                data: match item.item_type.get() {
+                    PouchItemType::Sword | PouchItemType::Bow | PouchItemType::Shield
+                    | PouchItemType::Food => item.data,
+                    _ => Data::default(),
+                },
+            });
+        }
+
+        Ok(file)
     }
 
     // Load file
-    pub fn load(&mut self, memory: &mut Memory, this: Pointer<Self>, file: GameData) {
-        self.update(memory, &this);
+    pub fn load(
+        self: Pointer<Self>, memory: &mut Memory, file: GameData
+    ) -> Result<Outcome, SimError> {
+        Outcome::catch(self.load_impl(memory, file))
+    }
+
+    fn load_impl(
+        self: Pointer<Self>, memory: &mut Memory, file: GameData
+    ) -> Result<(), SimError> {
+        // Return items to list2, leaving items past list1.count in place
+        loop {
+            let item_ptr = self.list1().pop_front(memory)?;
+            if item_ptr == Pointer::NULLPTR { break; }
+            self.list2().push_front(memory, item_ptr)?;
+        }
+        self.reset_item_and_pointers(memory)?;
+
+        for item in file.iter().take(NUM_POUCH_ITEMS_MAX as usize) {
+            let item_ptr = self.add_to_pouch(
+                memory, &item.name, item.item_type, item.value, item.equipped
+            )?;
+            if item_ptr == Pointer::NULLPTR { break; }
+
+            if let PouchItemType::Sword | PouchItemType::Bow | PouchItemType::Shield
+            | PouchItemType::Food = item.item_type {
+                field!(item_ptr, data).write(memory, Box::new(item.data))?;
+            }
+        }
+
+        self.update_inventory_info(memory)?;
+        self.update_list_heads(memory)?;
+        self.sync(memory)?;
+        Ok(())
+    }
+
+    // Build PMDM image at heap base from a save file instead of a console dump
+    pub fn create(
+        heap_base: u64, version: &GameVersion, file: GameData
+    ) -> Result<(Memory, Pointer<Self>), SimError> {
+        let this = Pointer::<Self>::new(heap_base + version.pmdm_heap_offset.unwrap_or(0));
+        let mut memory = Memory::init(vec![(this.address, vec![0; mem::size_of::<Self>()])]);
+
+        // Stand-in FixedSafeString vtable behind PMDM, as the main module is not dumped
+        let vtable = Pointer::<FixedSafeStringVTable>::new(
+            (this.address + mem::size_of::<Self>() as u64).next_multiple_of(0x10)
+        );
+        vtable.write(&mut memory, Box::new(FixedSafeStringVTable {
+            super_dtor: Guest::new(vtable.cast()),
+            super_assure_termination_impl: Guest::new(vtable.cast()),
+            dtor: Guest::new(vtable.cast()),
+            assure_termination_impl: Guest::new(vtable.cast()),
+        }))?;
+
+        // Item names need a vtable before their constructors can run
+        let items = (0..NUM_POUCH_ITEMS_MAX as usize).map(
+            |i| field!(this, item_lists.buffer.buffer[i])
+        ).chain([field!(this, newly_added_item)]);
+        for item_ptr in items {
+            field!(item_ptr, name.vptr).set(&mut memory, vtable.cast())?;
+        }
+
+        this.ctor(&mut memory)?;
+        this.load_impl(&mut memory, file)?;
+
+        // Sort once like picking up an item would, as files may list items in any order
+        this.update_after_adding_item(&mut memory)?;
+        Ok((memory, this))
     }
 
     // Break slots
-    pub fn offset(&mut self, memory: &mut Memory, this: Pointer<Self>, num: u32) {
-        let lists = self.item_lists;
-
-        (this.cast() + mem::offset_of!(Self, item_lists.list1.count) as u64).write(memory, Box::new(
-            (i32::from_le(lists.list1.count) - num as i32).to_le()
-        )).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, item_lists.list2.count) as u64).write(memory, Box::new(
-            (i32::from_le(lists.list2.count) + num as i32).to_le()
-        )).unwrap();
-        self.update(memory, &this);
+    pub fn offset(
+        self: Pointer<Self>, memory: &mut Memory, num: u32
+    ) -> Result<Outcome, SimError> {
+        Outcome::catch(self.offset_impl(memory, num))
+    }
+
+    fn offset_impl(
+        self: Pointer<Self>, memory: &mut Memory, num: u32
+    ) -> Result<(), SimError> {
+        let list1_count = field!(self.list1(), count);
+        let list2_count = field!(self.list2(), count);
+        // Counts wrap like the game's 32-bit arithmetic
+        list1_count.set(memory, list1_count.get(memory)?.wrapping_sub(num as i32))?;
+        list2_count.set(memory, list2_count.get(memory)?.wrapping_add(num as i32))?;
+        Ok(())
     }
 }
 
-impl Updatable for PauseMenuDataMgr {}
+impl Constructor for PauseMenuDataMgr {
+    fn ctor(self: Pointer<Self>, memory: &mut Memory) -> Result<(), SimError> {
+        field!(self, item_lists).ctor(memory)?;
+
+        field!(self, list_heads).write(memory, Box::new(SafeArray::default()))?;
+        field!(self, tabs).write(memory, Box::new(SafeArray::default()))?;
+        field!(self, tabs_type).write(memory, Box::new(SafeArray {
+            buffer: [RawEnum::new(PouchItemType::Invalid); NUM_TAB_MAX as usize]
+        }))?;
+        field!(self, last_added_item_tab).set(memory, i32::default())?;
+        field!(self, last_added_item_slot).set(memory, i32::default())?;
+        field!(self, num_tabs).set(memory, i32::default())?;
+        field!(self, grabbed_items).write(memory, Box::new(SafeArray::default()))?;
+        for soul_item in [
+            field!(self, rito_soul_item), field!(self, goron_soul_item),
+            field!(self, zora_soul_item), field!(self, gerudo_soul_item),
+        ] {
+            soul_item.set(memory, Pointer::NULLPTR)?;
+        }
+        field!(self, can_see_health_bar).set(memory, false)?;
+        field!(self, newly_added_item).ctor(memory)?;
+        field!(self, is_pouch_for_quest).set(memory, false)?;
+        field!(self, equipped_weapons).write(memory, Box::new(SafeArray::default()))?;
+        field!(self, category_to_sort).set(memory, PouchCategory::Invalid)?;
+
+        self.reset_item_and_pointers(memory)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::platform::*;
+
+    use super::*;
+
+    pub fn item(name: &str, item_type: PouchItemType, value: i32) -> GameDataItem {
+        GameDataItem {
+            name: name.to_string(), item_type, equipped: false, value, data: Data::default()
+        }
+    }
+
+    // PMDM built from a save file at the synthetic heap base
+    pub fn create_pmdm(file: GameData) -> (Memory, Pointer<PauseMenuDataMgr>) {
+        let version = GameVersion::default_for_platform();
+        PauseMenuDataMgr::create(SYNTHETIC_HEAP_BASE, version, file).unwrap()
+    }
+
+    // Names of the items linked in list1, including those past its count
+    pub fn list1_names(memory: &Memory, this: Pointer<PauseMenuDataMgr>) -> Vec<String> {
+        let list1 = this.list1().view(memory).unwrap();
+        let start_end = field!(this.list1(), start_end);
+        let mut node = list1.start_end.next.get();
+        let mut names = vec![];
+        while node != start_end && names.len() < NUM_POUCH_ITEMS_MAX as usize {
+            let item = list1.list_node_to_obj(node).view(memory).unwrap();
+            names.push(item.name.to_string());
+            node = item.list_node.next.get();
+        }
+        names
+    }
+
+    // Compare two items added to an empty pouch with sort_predicate
+    fn compare(lhs: (&str, PouchItemType), rhs: (&str, PouchItemType)) -> i32 {
+        let (mut memory, this) = create_pmdm(vec![]);
+        let lhs = this.add_to_pouch(&mut memory, lhs.0, lhs.1, 1, false).unwrap();
+        let rhs = this.add_to_pouch(&mut memory, rhs.0, rhs.1, 1, false).unwrap();
+        this.sort_predicate(&memory, lhs, rhs).unwrap()
+    }
+
+    #[test]
+    fn sort_predicate_orders_categories() {
+        use PouchItemType::*;
+        assert_eq!(compare(("Weapon_Sword_001", Sword), ("Weapon_Bow_001", Bow)), -1);
+        assert_eq!(compare(("Weapon_Shield_001", Shield), ("Armor_001_Head", ArmorHead)), -1);
+        assert_eq!(compare(("Armor_001_Lower", ArmorLower), ("Item_Fruit_A", Material)), -1);
+        assert_eq!(compare(("Item_Fruit_A", Material), ("Item_Cook_A_01", Food)), -1);
+        assert_eq!(compare(("Obj_KorokNuts", KeyItem), ("Item_Roast_01", Food)), 1);
+        assert_eq!(compare(("NormalArrow", Arrow), ("Weapon_Shield_001", Shield)), -1);
+    }
+
+    #[test]
+    fn sort_predicate_orders_within_categories() {
+        use PouchItemType::*;
+        // Bows before arrows
+        assert_eq!(compare(("NormalArrow", Arrow), ("Weapon_Bow_001", Bow)), 1);
+        assert_eq!(compare(("NormalArrow", Arrow), ("FireArrow", Arrow)), -1);
+
+        // Weapons by use, then number
+        assert_eq!(compare(("Weapon_Lsword_001", Sword), ("Weapon_Sword_002", Sword)), 1);
+        assert_eq!(compare(("Weapon_Lsword_002", Sword), ("Weapon_Spear_001", Sword)), -1);
+        assert_eq!(compare(("Weapon_Sword_031", Sword), ("Weapon_Sword_002", Sword)), 1);
+
+        // Armor by set, then slot
+        assert_eq!(compare(("Armor_001_Lower", ArmorLower), ("Armor_002_Head", ArmorHead)), -1);
+        assert_eq!(compare(("Armor_005_Upper", ArmorUpper), ("Armor_005_Head", ArmorHead)), 1);
+
+        // Materials by table, with unlisted ones after and equal to each other
+        assert_eq!(compare(("Item_Fruit_G", Material), ("Item_Fruit_B", Material)), -1);
+        assert_eq!(compare(("Item_Unlisted", Material), ("FireWood", Material)), 1);
+        assert_eq!(compare(("Item_Unlisted", Material), ("Item_Other", Material)), 0);
+
+        // Cooked meals first, in the order they were made
+        assert_eq!(compare(("Item_Roast_01", Food), ("Item_Cook_A_01", Food)), 1);
+        assert_eq!(compare(("Item_Cook_B_02", Food), ("Item_Cook_A_01", Food)), 0);
+
+        // Other food by number, which is not the game's order
+        assert_eq!(compare(("Item_Roast_03", Food), ("Item_Chilled_01", Food)), 1);
+
+        assert_eq!(compare(("Obj_KorokNuts", KeyItem), ("PlayerStole2", KeyItem)), 1);
+    }
+
+    // Type and first item of each tab
+    fn tabs(memory: &Memory, this: Pointer<PauseMenuDataMgr>) -> Vec<(PouchItemType, String)> {
+        let pmdm = this.view(memory).unwrap();
+        (0..pmdm.num_tabs.get() as usize).map(|i| (
+            pmdm.tabs_type.buffer[i].get(),
+            pmdm.tabs.buffer[i].get().view(memory).unwrap().name.to_string(),
+        )).collect()
+    }
+
+    // Tab each category's list head points at
+    fn list_heads(memory: &Memory, this: Pointer<PauseMenuDataMgr>) -> Vec<Option<usize>> {
+        this.view(memory).unwrap().list_heads.buffer.iter().map(|head| {
+            (0..NUM_TAB_MAX as usize).find(|&i| head.get() == field!(this, tabs.buffer[i]))
+        }).collect()
+    }
+
+    #[test]
+    fn tabs_follow_categories() {
+        use PouchItemType::*;
+        let (memory, this) = create_pmdm(vec![
+            item("Weapon_Sword_001", Sword, 10),
+            item("Weapon_Bow_001", Bow, 10),
+            item("NormalArrow", Arrow, 10),
+            item("Item_Fruit_A", Material, 1),
+            item("Obj_KorokNuts", KeyItem, 1),
+        ]);
+        assert_eq!(tabs(&memory, this), [
+            (Sword, "Weapon_Sword_001".to_string()),
+            (Bow, "Weapon_Bow_001".to_string()),
+            (Material, "Item_Fruit_A".to_string()),
+            (KeyItem, "Obj_KorokNuts".to_string()),
+        ]);
+        assert_eq!(
+            list_heads(&memory, this), [Some(0), Some(1), None, None, Some(2), None, Some(3)]
+        );
+    }
+
+    #[test]
+    fn tabs_split_when_full() {
+        let num = NUM_ITEMS_PER_TAB as usize;
+        let (memory, this) = create_pmdm(MATERIAL_ORDER[..num + 1].iter().map(
+            |name| item(name, PouchItemType::Material, 1)
+        ).collect());
+        let tabs = tabs(&memory, this);
+        assert_eq!(tabs.len(), 2);
+        assert_eq!(tabs[1].1, MATERIAL_ORDER[num]);
+        assert_eq!(list_heads(&memory, this)[PouchCategory::Material.raw() as usize], Some(0));
+    }
+
+    #[test]
+    fn tabs_include_items_past_count() {
+        let (mut memory, this) = create_pmdm(vec![
+            item("Weapon_Sword_001", PouchItemType::Sword, 10),
+            item("Item_Fruit_A", PouchItemType::Material, 1),
+            item("Obj_KorokNuts", PouchItemType::KeyItem, 1),
+        ]);
+        let count = field!(this.list1(), count);
+        count.set(&mut memory, 1).unwrap();
+        this.update_inventory_info(&mut memory).unwrap();
+        this.update_list_heads(&mut memory).unwrap();
+        assert_eq!(tabs(&memory, this).len(), 3);
+        assert_eq!(
+            list_heads(&memory, this), [Some(0), None, None, None, Some(1), None, Some(2)]
+        );
+
+        // Without a count there is no first item to start from
+        count.set(&mut memory, 0).unwrap();
+        this.update_inventory_info(&mut memory).unwrap();
+        this.update_list_heads(&mut memory).unwrap();
+        assert_eq!(tabs(&memory, this), []);
+        assert_eq!(list_heads(&memory, this), [None; NUM_POUCH_CATEGORIES as usize]);
+    }
+
+    // Saved items as comparable fields, reading data as cook data as it spans all of it
+    fn saved_fields(file: &GameData) -> Vec<(String, PouchItemType, bool, i32, [u32; 5])> {
+        file.iter().map(|item| {
+            let cook = unsafe { item.data.cook };
+            (item.name.clone(), item.item_type, item.equipped, item.value, [
+                cook.health_recover.get() as u32, cook.effect_duration.get() as u32,
+                cook.sell_price.get() as u32, cook.effect_id.get().to_bits(),
+                cook.effect_level.get().to_bits(),
+            ])
+        }).collect()
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let mut sword = item("Weapon_Sword_001", PouchItemType::Sword, 10);
+        sword.equipped = true;
+        let mut weapon = WeaponData::default();
+        weapon.modifier = Guest::new(0x1);
+        weapon.modifier_value = Guest::new(5);
+        sword.data = Data { weapon };
+        let mut meal = item("Item_Cook_A_01", PouchItemType::Food, 1);
+        meal.data = Data { cook: CookData {
+            health_recover: Guest::new(12), effect_id: Guest::new(3.0), ..CookData::default()
+        } };
+        let file = vec![
+            sword, item("Item_Fruit_A", PouchItemType::Material, 3), meal,
+            item("Obj_KorokNuts", PouchItemType::KeyItem, 1),
+        ];
+
+        let (mut memory, this) = create_pmdm(file.clone());
+        let Outcome::Done(saved) = this.save(&memory).unwrap() else { panic!("save froze") };
+        assert_eq!(saved_fields(&saved), saved_fields(&file));
+
+        let no_modifier = Pointer::NULLPTR;
+        this.get(&mut memory, "Weapon_Bow_001", PouchItemType::Bow, 10, no_modifier).unwrap();
+        this.load(&mut memory, saved).unwrap();
+        let Outcome::Done(reloaded) = this.save(&memory).unwrap() else { panic!("save froze") };
+        assert_eq!(saved_fields(&reloaded), saved_fields(&file));
+    }
+
+    #[test]
+    fn load_keeps_items_past_count() {
+        let (mut memory, this) = create_pmdm(vec![
+            item("Weapon_Sword_001", PouchItemType::Sword, 10),
+            item("Item_Fruit_A", PouchItemType::Material, 1),
+            item("Obj_KorokNuts", PouchItemType::KeyItem, 1),
+        ]);
+        this.offset(&mut memory, 1).unwrap();
+        let list1 = this.list1().view(&memory).unwrap();
+        let broken = list1.list_node_to_obj(list1.start_end.prev.get());
+
+        // The broken slot is saved, and stays linked in front of the reloaded items
+        let Outcome::Done(saved) = this.save(&memory).unwrap() else { panic!("save froze") };
+        assert_eq!(saved.len(), 3);
+        this.load(&mut memory, saved).unwrap();
+        assert_eq!(
+            list1_names(&memory, this),
+            ["Obj_KorokNuts", "Weapon_Sword_001", "Item_Fruit_A", "Obj_KorokNuts"]
+        );
+        let list1 = this.list1().view(&memory).unwrap();
+        assert!(list1.list_node_to_obj(list1.start_end.next.get()) == broken);
+        assert_eq!(list1.count.get(), 3);
+    }
+
+    #[test]
+    fn create_sorts_items() {
+        let (memory, this) = create_pmdm(vec![
+            item("Item_Fruit_A", PouchItemType::Material, 5),
+            item("NormalArrow", PouchItemType::Arrow, 20),
+            item("Weapon_Sword_001", PouchItemType::Sword, 10),
+        ]);
+        assert_eq!(list1_names(&memory, this), ["Weapon_Sword_001", "NormalArrow", "Item_Fruit_A"]);
+        assert_eq!(tabs(&memory, this), [
+            (PouchItemType::Sword, "Weapon_Sword_001".to_string()),
+            (PouchItemType::Arrow, "NormalArrow".to_string()),
+            (PouchItemType::Material, "Item_Fruit_A".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn get_sorts_new_item_in() {
+        let (mut memory, this) = create_pmdm(vec![
+            item("Weapon_Sword_002", PouchItemType::Sword, 10),
+            item("Item_Fruit_B", PouchItemType::Material, 1),
+        ]);
+        let no_modifier = Pointer::NULLPTR;
+        this.get(&mut memory, "Item_Fruit_G", PouchItemType::Material, 1, no_modifier).unwrap();
+        this.get(&mut memory, "Weapon_Sword_001", PouchItemType::Sword, 10, no_modifier).unwrap();
+        assert_eq!(
+            list1_names(&memory, this),
+            ["Weapon_Sword_001", "Weapon_Sword_002", "Item_Fruit_G", "Item_Fruit_B"]
+        );
+    }
+
+    #[test]
+    fn sort_predicate_only_compares_category_to_sort() {
+        let (mut memory, this) = create_pmdm(vec![]);
+        let sword = this.add_to_pouch(
+            &mut memory, "Weapon_Sword_002", PouchItemType::Sword, 1, false
+        ).unwrap();
+        let apple = this.add_to_pouch(
+            &mut memory, "Item_Fruit_A", PouchItemType::Material, 1, false
+        ).unwrap();
+        let mushroom = this.add_to_pouch(
+            &mut memory, "Item_Mushroom_N", PouchItemType::Material, 1, false
+        ).unwrap();
+        field!(this, category_to_sort).set(&mut memory, PouchCategory::Material).unwrap();
+
+        assert_eq!(this.sort_predicate(&memory, apple, sword).unwrap(), 0);
+        assert_eq!(this.sort_predicate(&memory, mushroom, apple).unwrap(), 1);
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt::Write;
+use std::mem;
+
+use crate::error::*;
+use crate::mem::*;
+use crate::pmdm::*;
+use crate::types::*;
+
+fn category_name(category: PouchCategory) -> &'static str {
+    match category {
+        PouchCategory::Sword => "Weapons",
+        PouchCategory::Bow => "Bows and Arrows",
+        PouchCategory::Shield => "Shields",
+        PouchCategory::Armor => "Armor",
+        PouchCategory::Material => "Materials",
+        PouchCategory::Food => "Food",
+        PouchCategory::KeyItem => "Key Items",
+        PouchCategory::Invalid => "Invalid",
+        PouchCategory::Unknown(_) => "Unknown",
+    }
+}
+
+// Format one slot with its list1 index, equipped marker, name and durability or count
+fn render_item(
+    out: &mut String, index: usize, item: &PouchItem, translations: &serde_json::Value, flag: &str
+) {
+    let actor_name = item.name.to_string();
+    let name = translate_name(&actor_name, translations).unwrap_or(actor_name);
+    let value = item.value.get();
+    let value = match item.item_type.get() {
+        PouchItemType::Sword | PouchItemType::Bow | PouchItemType::Shield => {
+            format!("durability {}", value)
+        },
+        PouchItemType::ArmorHead | PouchItemType::ArmorUpper | PouchItemType::ArmorLower => {
+            String::new()
+        },
+        // Show the raw type of corrupted slots alongside their value
+        PouchItemType::Unknown(item_type) => format!("x{} (type {})", value, item_type),
+        _ => format!("x{}", value),
+    };
+
+    let line = format!(
+        "  {:>3} {} {:<40} {:<16}{}{}", index, if item.equipped.get() { "[E]" } else { "   " },
+        name, value, if item.in_inventory.get() { "" } else { " (dropped)" }, flag
+    );
+    let _ = writeln!(out, "{}", line.trim_end());
+}
+
+// Lay out list1 under the tabs PMDM last computed, flagging items the menu would not show. The
+// stored tabs are used as is, so they show stale after slots are removed or broken.
+pub fn render_inventory(
+    memory: &Memory, this: Pointer<PauseMenuDataMgr>, translations: &serde_json::Value
+) -> Result<String, SimError> {
+    let pmdm = this.view(memory)?;
+    let list1 = &pmdm.item_lists.list1;
+    let count = list1.count.get();
+    let list2_count = pmdm.item_lists.list2.count.get();
+    let start_end = field!(this, item_lists.list1.start_end);
+    let num_tabs = pmdm.num_tabs.get().clamp(0, NUM_TAB_MAX) as usize;
+    let tabs = &pmdm.tabs.buffer[..num_tabs];
+    let mut out = String::new();
+
+    // Walk the whole chain, as broken slots are linked past list1.count. An unreadable node
+    // ends the walk, as its next link can't be followed.
+    let mut items = vec![];
+    let mut node = list1.start_end.next.get();
+    while node != start_end && items.len() < NUM_POUCH_ITEMS_MAX as usize {
+        let item_ptr = list1.list_node_to_obj(node);
+        let item = item_ptr.view(memory).ok();
+        if let Some(item) = &item { node = item.list_node.next.get(); }
+        let readable = item.is_some();
+        items.push((item_ptr, item));
+        if !readable { break; }
+    }
+
+    let mut tab = None;
+    let mut num_items_in_tab = 0;
+    for (i, (item_ptr, item)) in items.iter().enumerate() {
+        if i == count.max(0) as usize { let _ = writeln!(out, "[Broken slots]"); }
+        for (t, _) in tabs.iter().enumerate().filter(|(_, start)| start.get() == *item_ptr) {
+            let category = pmdm.tabs_type.buffer[t].get().category();
+            let _ = writeln!(out, "[Tab {}: {}]", t, category_name(category));
+            tab = Some(t);
+            num_items_in_tab = 0;
+        }
+        num_items_in_tab += 1;
+
+        let flag = if i >= count.max(0) as usize { " (out of list)" } else if tab.is_none() {
+            " (no tab)"
+        } else if num_items_in_tab > NUM_ITEMS_PER_TAB { " (past end of tab)" } else { "" };
+        match item {
+            Some(item) => render_item(&mut out, i, item, translations, flag),
+            None => {
+                let _ = writeln!(
+                    out, "  {:>3}     0x{:x} (unreadable){}", i, item_ptr.address, flag
+                );
+            },
+        }
+    }
+
+    for (t, start) in tabs.iter().enumerate() {
+        if !items.iter().any(|(item_ptr, _)| *item_ptr == start.get()) {
+            let _ = writeln!(
+                out, "Tab {} starts at 0x{:x}, which is not in list1", t, start.get().address
+            );
+        }
+    }
+    if node != start_end && items.last().is_some_and(|(_, item)| item.is_some()) {
+        let _ = writeln!(out, "list1 does not lead back to its start within {} items", items.len());
+    }
+    if items.len() < count.max(0) as usize {
+        let _ = writeln!(out, "list1 links {} items but its count is {}", items.len(), count);
+    }
+    if count.wrapping_add(list2_count) != NUM_POUCH_ITEMS_MAX {
+        let _ = writeln!(
+            out, "list1 count {} and list2 count {} do not add up to {}",
+            count, list2_count, NUM_POUCH_ITEMS_MAX
+        );
+    }
+
+    Ok(out)
+}
+
+// Escape text for a quoted DOT string
+fn dot_escape(text: &str) -> String { text.replace('\\', "\\\\").replace('"', "\\\"") }
+
+// Draw list1, list2, list_heads and tabs as a Graphviz graph. Nodes are found by following
+// both next and prev links, so nodes reached only one way in a tangled list still show up.
+pub fn render_dot(
+    memory: &Memory, this: Pointer<PauseMenuDataMgr>, translations: &serde_json::Value
+) -> Result<String, SimError> {
+    let pmdm = this.view(memory)?;
+    let sentinels = [
+        (field!(this.list1(), start_end), "list1", pmdm.item_lists.list1.count.get()),
+        (field!(this.list2(), start_end), "list2", pmdm.item_lists.list2.count.get()),
+    ];
+    let tab_size = mem::size_of::<Guest<Pointer<PouchItem>>>() as u64;
+    let tabs_start = field!(this, tabs.buffer[0]).address;
+    let mut out = String::new();
+    let _ = writeln!(out, "digraph PauseMenuDataMgr {{");
+    let _ = writeln!(out, "    node [shape=box, fontname=monospace];");
+
+    // Tab each list head points into, if it points into tabs at all
+    let heads: Vec<Pointer<Guest<Pointer<PouchItem>>>> = pmdm.list_heads.buffer.iter().map(
+        |head| head.get()
+    ).collect();
+    let head_tab = |head: Pointer<Guest<Pointer<PouchItem>>>| {
+        let offset = head.address.checked_sub(tabs_start)?;
+        (offset.is_multiple_of(tab_size) && offset / tab_size < NUM_TAB_MAX as u64)
+            .then_some((offset / tab_size) as usize)
+    };
+
+    // Show tabs in use, plus any a list head points at
+    let num_tabs = pmdm.num_tabs.get().clamp(0, NUM_TAB_MAX) as usize;
+    let tab_rows: Vec<usize> = (0..NUM_TAB_MAX as usize).filter(|&i| {
+        i < num_tabs || pmdm.tabs.buffer[i].get() != Pointer::NULLPTR
+            || heads.iter().any(|&head| head_tab(head) == Some(i))
+    }).collect();
+    let rows: Vec<String> = tab_rows.iter().map(|&i| format!(
+        "<t{}> {}: {}", i, i, category_name(pmdm.tabs_type.buffer[i].get().category())
+    )).collect();
+    let _ = writeln!(out, "    tabs [shape=record, label=\"{{tabs|{}}}\"];", rows.join("|"));
+
+    let rows: Vec<String> = (0..NUM_POUCH_CATEGORIES).map(|i| format!(
+        "<h{}> {}", i, category_name(PouchCategory::from_raw(i))
+    )).collect();
+    let _ = writeln!(
+        out, "    list_heads [shape=record, label=\"{{list_heads|{}}}\"];", rows.join("|")
+    );
+
+    for (category, &head) in heads.iter().enumerate() {
+        if head == Pointer::NULLPTR { continue; }
+        match head_tab(head) {
+            Some(tab) => {
+                let _ = writeln!(out, "    list_heads:h{} -> tabs:t{};", category, tab);
+            },
+            None => {
+                let _ = writeln!(
+                    out, "    p{:x} [label=\"0x{:x}\", style=dashed];", head.address, head.address
+                );
+                let _ = writeln!(out, "    list_heads:h{} -> p{:x};", category, head.address);
+            },
+        }
+    }
+
+    let mut queue: VecDeque<Pointer<ListNode>> = sentinels.iter().map(
+        |&(node, _, _)| node
+    ).collect();
+    for &i in &tab_rows {
+        let item_ptr = pmdm.tabs.buffer[i].get();
+        if item_ptr == Pointer::NULLPTR { continue; }
+        let node = field!(item_ptr, list_node);
+        let _ = writeln!(out, "    tabs:t{} -> n{:x};", i, node.address);
+        queue.push_back(node);
+    }
+
+    // Visit at most every buffer item and both sentinels, plus as many stray nodes
+    let mut visited = BTreeSet::new();
+    let max_nodes = 2 * (NUM_POUCH_ITEMS_MAX as usize + sentinels.len());
+    while let Some(node) = queue.pop_front() {
+        if visited.len() >= max_nodes || !visited.insert(node.address) { continue; }
+
+        let item_ptr = pmdm.item_lists.list1.list_node_to_obj(node);
+        let sentinel = sentinels.iter().find(|&&(sentinel, _, _)| sentinel == node);
+        let label = if let Some(&(_, name, count)) = sentinel {
+            format!("{}.start_end\\ncount {}", name, count)
+        } else if let (Some(index), Ok(item)) = (this.item_index(item_ptr), item_ptr.view(memory)) {
+            let actor_name = item.name.to_string();
+            let name = translate_name(&actor_name, translations).unwrap_or(actor_name);
+            format!("[{}] {}\\nvalue {}", index, dot_escape(&name), item.value.get())
+        } else { format!("0x{:x}", node.address) };
+
+        let Ok(list_node) = node.view(memory) else {
+            let _ = writeln!(
+                out, "    n{:x} [label=\"{} (unreadable)\", style=dashed];", node.address, label
+            );
+            continue;
+        };
+        let _ = writeln!(out, "    n{:x} [label=\"{}\"];", node.address, label);
+
+        let next = list_node.next.get();
+        if next != Pointer::NULLPTR {
+            let _ = writeln!(out, "    n{:x} -> n{:x};", node.address, next.address);
+            queue.push_back(next);
+        }
+        let prev = list_node.prev.get();
+        if prev != Pointer::NULLPTR {
+            let _ = writeln!(
+                out, "    n{:x} -> n{:x} [style=dashed, color=gray];", node.address, prev.address
+            );
+            queue.push_back(prev);
+        }
+    }
+
+    let _ = writeln!(out, "}}");
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::platform::*;
+    use crate::version::*;
+
+    use super::*;
+
+    // PMDM holding one apple, with list1 leading into a stray node below the list offset
+    fn stray_node_pmdm() -> (Memory, Pointer<PauseMenuDataMgr>) {
+        let (mut memory, this) = PauseMenuDataMgr::create(
+            SYNTHETIC_HEAP_BASE, GameVersion::default_for_platform(), vec![GameDataItem {
+                name: "Item_Fruit_A".to_string(), item_type: PouchItemType::Material,
+                equipped: false, value: 5, data: Data::default(),
+            }]
+        ).unwrap();
+        let item_ptr = this.view(&memory).unwrap().item_lists.list1.nth(&memory, 0).unwrap();
+        field!(item_ptr, list_node.next).set(&mut memory, Pointer::new(0x4)).unwrap();
+        (memory, this)
+    }
+
+    #[test]
+    fn inventory_flags_stray_node() {
+        let (memory, this) = stray_node_pmdm();
+        let out = render_inventory(&memory, this, &serde_json::Value::Null).unwrap();
+        assert!(out.contains("Item_Fruit_A"), "{}", out);
+        assert!(out.contains("(unreadable) (out of list)"), "{}", out);
+    }
+
+    #[test]
+    fn dot_shows_stray_node() {
+        let (memory, this) = stray_node_pmdm();
+        let out = render_dot(&memory, this, &serde_json::Value::Null).unwrap();
+        assert!(out.contains("n4 [label=\"0x4 (unreadable)\", style=dashed];"), "{}", out);
+    }
+}
@@ -0,0 +1,363 @@
+use crate::error::*;
+use crate::mem::*;
+use crate::pmdm::*;
+use crate::render::*;
+use crate::types::*;
+
+pub enum Assertion {
+    // Number of items in list1
+    Count(i32),
+    // Item in list1 slot, optionally with its value
+    Slot { slot: i32, name: String, value: Option<i32> },
+}
+
+pub enum Action {
+    Get { name: String, item_type: PouchItemType, value: i32 },
+    Drop(i32),
+    Equip(i32),
+    Unequip(i32),
+    Pause,
+    Unpause,
+    Save,
+    Load,
+    Break(u32),
+    Assert(Assertion),
+    Undo,
+    Redo,
+}
+
+pub struct Step {
+    pub line: usize,
+    pub text: String,
+    pub action: Action,
+}
+
+fn bad_script(line: usize, message: impl Into<String>) -> SimError {
+    SimError::BadScript { line, message: message.into() }
+}
+
+fn parse_number<N: std::str::FromStr>(line: usize, word: Option<&str>) -> Result<N, SimError> {
+    let word = word.ok_or(bad_script(line, "missing number"))?;
+    word.parse().map_err(|_| bad_script(line, format!("invalid number '{}'", word)))
+}
+
+fn parse_item_type(line: usize, word: Option<&str>) -> Result<PouchItemType, SimError> {
+    let word = word.ok_or(bad_script(line, "missing item type"))?;
+    serde_json::from_value(serde_json::Value::from(word)).map_err(
+        |_| bad_script(line, format!("invalid item type '{}'", word))
+    )
+}
+
+// Parse script with one action per line, ignoring blank lines and # comments
+pub fn parse_script(source: &str) -> Result<Vec<Step>, SimError> {
+    let mut steps = vec![];
+
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let text = text.split('#').next().unwrap_or_default().trim();
+        let mut words = text.split_whitespace();
+        let Some(command) = words.next() else { continue; };
+
+        let action = match command {
+            "get" => Action::Get {
+                name: words.next().ok_or(bad_script(line, "missing item name"))?.to_string(),
+                item_type: parse_item_type(line, words.next())?,
+                value: parse_number(line, words.next())?,
+            },
+            "drop" => Action::Drop(parse_number(line, words.next())?),
+            "equip" => Action::Equip(parse_number(line, words.next())?),
+            "unequip" => Action::Unequip(parse_number(line, words.next())?),
+            "pause" => Action::Pause,
+            "unpause" => Action::Unpause,
+            "save" => Action::Save,
+            "load" => Action::Load,
+            "break" => Action::Break(parse_number(line, words.next())?),
+            "undo" => Action::Undo,
+            "redo" => Action::Redo,
+            "assert" => Action::Assert(match words.next() {
+                Some("count") => Assertion::Count(parse_number(line, words.next())?),
+                Some("slot") => Assertion::Slot {
+                    slot: parse_number(line, words.next())?,
+                    name: words.next().ok_or(bad_script(line, "missing item name"))?.to_string(),
+                    value: words.next().map(|word| parse_number(line, Some(word))).transpose()?,
+                },
+                _ => return Err(bad_script(line, "expected 'assert count' or 'assert slot'")),
+            }),
+            _ => return Err(bad_script(line, format!("unknown action '{}'", command))),
+        };
+
+        if words.next().is_some() { return Err(bad_script(line, "too many arguments")); }
+        steps.push(Step { line, text: text.to_string(), action });
+    }
+
+    Ok(steps)
+}
+
+// Get item in list1 slot
+fn item_at(
+    memory: &Memory, this: Pointer<PauseMenuDataMgr>, line: usize, slot: i32
+) -> Result<Pointer<PouchItem>, SimError> {
+    let item_ptr = this.view(memory)?.item_lists.list1.nth(memory, slot)?;
+    if item_ptr == Pointer::NULLPTR {
+        return Err(bad_script(line, format!("no item in slot {}", slot)));
+    }
+    Ok(item_ptr)
+}
+
+// Simulator state kept outside guest memory, carried across runs in snapshots
+#[derive(Clone, Default)]
+pub struct ScriptState {
+    pub paused: bool,
+    pub save_file: Option<GameData>,
+}
+
+// Action that can be undone, with the memory it changed and the state around it
+struct HistoryEntry {
+    delta: Delta,
+    before: ScriptState,
+    after: ScriptState,
+}
+
+// Actions to undo and redo, most recent last
+#[derive(Default)]
+struct History {
+    undo: Vec<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+}
+
+// Run script against PMDM starting from state, printing the inventory after each step
+pub fn run_script(
+    memory: &mut Memory, this: Pointer<PauseMenuDataMgr>, steps: &[Step],
+    translations: &serde_json::Value, state: &mut ScriptState
+) -> Result<Outcome, SimError> {
+    let mut history = History::default();
+
+    for step in steps {
+        println!("> {}", step.text);
+        memory.set_trace_action(format!("line {} ({})", step.line, step.text));
+
+        let outcome = match &step.action {
+            Action::Assert(assertion) => {
+                check_assertion(memory, this, step.line, assertion)?;
+                Outcome::Done(())
+            },
+            Action::Undo => {
+                let entry = history.undo.pop().ok_or(bad_script(step.line, "nothing to undo"))?;
+                memory.undo(&entry.delta)?;
+                *state = entry.before.clone();
+                history.redo.push(entry);
+                Outcome::Done(())
+            },
+            Action::Redo => {
+                let entry = history.redo.pop().ok_or(bad_script(step.line, "nothing to redo"))?;
+                memory.redo(&entry.delta)?;
+                *state = entry.after.clone();
+                history.undo.push(entry);
+                Outcome::Done(())
+            },
+            // Record what the action changes, whether or not it succeeds
+            _ => {
+                let before = state.clone();
+                memory.begin_delta();
+                let result = run_action(memory, this, step, state);
+                let delta = memory.end_delta();
+                history.undo.push(HistoryEntry { delta, before, after: state.clone() });
+                history.redo.clear();
+                result?
+            },
+        };
+
+        if let Outcome::Frozen { .. } = outcome { return Ok(outcome); }
+        print!("{}", render_inventory(memory, this, translations)?);
+    }
+
+    Ok(Outcome::Done(()))
+}
+
+// Run action that changes the game
+fn run_action(
+    memory: &mut Memory, this: Pointer<PauseMenuDataMgr>, step: &Step, state: &mut ScriptState
+) -> Result<Outcome, SimError> {
+    Ok(match &step.action {
+        Action::Get { name, item_type, value } => this.get(
+            memory, name, *item_type, *value, Pointer::NULLPTR
+        )?,
+        // Dropping while unpaused removes the slot straight away
+        Action::Drop(slot) => {
+            let item_ptr = item_at(memory, this, step.line, *slot)?;
+            if state.paused { this.drop(memory, item_ptr)? } else {
+                this.remove(memory, item_ptr)?
+            }
+        },
+        Action::Equip(slot) => {
+            let item_ptr = item_at(memory, this, step.line, *slot)?;
+            this.equip(memory, item_ptr)?
+        },
+        Action::Unequip(slot) => {
+            let item_ptr = item_at(memory, this, step.line, *slot)?;
+            this.unequip(memory, item_ptr)?
+        },
+        Action::Pause => {
+            state.paused = true;
+            this.pause(memory)?
+        },
+        // Items dropped in the menu leave the pouch on unpause
+        Action::Unpause => {
+            state.paused = false;
+            let list1 = &this.view(memory)?.item_lists.list1;
+            let mut dropped_items = vec![];
+            for slot in 0..list1.count.get() {
+                let item_ptr = list1.nth(memory, slot)?;
+                if item_ptr != Pointer::NULLPTR && !item_ptr.view(memory)?.in_inventory.get() {
+                    dropped_items.push(item_ptr);
+                }
+            }
+
+            let mut outcome = Outcome::Done(());
+            for item_ptr in dropped_items {
+                outcome = this.remove(memory, item_ptr)?;
+                if let Outcome::Frozen { .. } = outcome { break; }
+            }
+            outcome
+        },
+        Action::Save => match this.save(memory)? {
+            Outcome::Done(file) => {
+                state.save_file = Some(file);
+                Outcome::Done(())
+            },
+            Outcome::Frozen { list, cycle_nodes } => Outcome::Frozen { list, cycle_nodes },
+        },
+        Action::Load => {
+            let file = state.save_file.clone().ok_or(bad_script(step.line, "nothing saved"))?;
+            this.load(memory, file)?
+        },
+        Action::Break(num) => this.offset(memory, *num)?,
+        Action::Assert(_) | Action::Undo | Action::Redo => unreachable!(),
+    })
+}
+
+fn check_assertion(
+    memory: &Memory, this: Pointer<PauseMenuDataMgr>, line: usize, assertion: &Assertion
+) -> Result<(), SimError> {
+    let failed = |message: String| SimError::AssertionFailed { line, message };
+
+    match assertion {
+        Assertion::Count(expected) => {
+            let count = this.view(memory)?.item_lists.list1.count.get();
+            if count != *expected {
+                return Err(failed(format!("expected {} items, found {}", expected, count)));
+            }
+        },
+        Assertion::Slot { slot, name, value } => {
+            let item = item_at(memory, this, line, *slot)?.view(memory)?;
+            let actor_name = item.name.to_string();
+            if actor_name != *name {
+                return Err(failed(format!(
+                    "expected {} in slot {}, found {}", name, slot, actor_name
+                )));
+            }
+            if let Some(expected) = value && item.value.get() != *expected {
+                return Err(failed(format!(
+                    "expected value {} in slot {}, found {}", expected, slot,
+                    item.value.get()
+                )));
+            }
+        },
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::platform::*;
+    use crate::version::*;
+
+    use super::*;
+
+    fn item(name: &str, item_type: PouchItemType, value: i32, equipped: bool) -> GameDataItem {
+        GameDataItem { name: name.to_string(), item_type, equipped, value, data: Data::default() }
+    }
+
+    // Memory after running source on a small inventory
+    fn run(source: &str) -> Vec<(u64, Vec<u8>)> {
+        let (mut memory, this) = PauseMenuDataMgr::create(
+            SYNTHETIC_HEAP_BASE, GameVersion::default_for_platform(), vec![
+                item("Weapon_Sword_001", PouchItemType::Sword, 2200, true),
+                item("NormalArrow", PouchItemType::Arrow, 20, false),
+                item("Item_Fruit_A", PouchItemType::Material, 5, false),
+            ]
+        ).unwrap();
+        let steps = parse_script(source).unwrap();
+        let mut state = ScriptState::default();
+        run_script(&mut memory, this, &steps, &serde_json::Value::Null, &mut state).unwrap();
+        memory.regions().map(|(address, data)| (address, data.to_vec())).collect()
+    }
+
+    // Check that undoing action after setup restores memory byte for byte, and redoing it
+    // brings back what the action did
+    fn check_undo_redo(setup: &str, action: &str) {
+        let before = run(setup);
+        let after = run(&format!("{}\n{}", setup, action));
+        assert_ne!(before, after, "{} changes nothing", action);
+        assert!(before == run(&format!("{}\n{}\nundo", setup, action)), "undo of {}", action);
+        assert!(after == run(&format!("{}\n{}\nundo\nredo", setup, action)), "redo of {}", action);
+    }
+
+    #[test]
+    fn undo_redo_get() {
+        check_undo_redo("", "get Item_Fruit_A Material 3");
+        check_undo_redo("", "get Weapon_Sword_070 Sword 4000");
+    }
+
+    #[test]
+    fn undo_redo_load() {
+        check_undo_redo("save\nget Weapon_Sword_002 Sword 10\ndrop 0", "load");
+    }
+
+    #[test]
+    fn undo_redo_break() {
+        check_undo_redo("", "break 1");
+        check_undo_redo("break 2\nget Item_Fruit_B Material 1", "break 1");
+    }
+
+    #[test]
+    fn undo_restores_script_state() {
+        // Undoing the save leaves nothing to load
+        let steps = parse_script("save\nundo\nload").unwrap();
+        let (mut memory, this) = PauseMenuDataMgr::create(
+            SYNTHETIC_HEAP_BASE, GameVersion::default_for_platform(), vec![]
+        ).unwrap();
+        let mut state = ScriptState::default();
+        let result = run_script(&mut memory, this, &steps, &serde_json::Value::Null, &mut state);
+        assert!(matches!(result, Err(SimError::BadScript { line: 3, .. })));
+    }
+
+    #[test]
+    fn cycle_in_list1_freezes() {
+        for source in ["pause", "get Item_Fruit_C Material 1", "save"] {
+            let (mut memory, this) = PauseMenuDataMgr::create(
+                SYNTHETIC_HEAP_BASE, GameVersion::default_for_platform(), vec![
+                    item("Item_Fruit_A", PouchItemType::Material, 5, false),
+                    item("Item_Fruit_B", PouchItemType::Material, 5, false),
+                ]
+            ).unwrap();
+            let item_ptr = this.list1().view(&memory).unwrap().nth(&memory, 1).unwrap();
+            let node = field!(item_ptr, list_node);
+            field!(node, next).set(&mut memory, node).unwrap();
+
+            let steps = parse_script(source).unwrap();
+            let mut state = ScriptState::default();
+            let outcome = run_script(
+                &mut memory, this, &steps, &serde_json::Value::Null, &mut state
+            ).unwrap();
+            let Outcome::Frozen { list, cycle_nodes } = outcome else {
+                panic!("{} did not freeze", source);
+            };
+            assert_eq!(list, ItemList::List1);
+            assert_eq!(cycle_nodes.len(), 1);
+            assert_eq!(cycle_nodes[0].address, node.address);
+            assert_eq!(cycle_nodes[0].name.as_deref(), Some("Item_Fruit_B"));
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::any;
+use std::fmt::Write;
+use std::mem;
+use std::ops::Range;
+
+use crate::mem::*;
+
+// Named field of a guest struct
+pub struct FieldSymbol {
+    pub name: &'static str,
+    pub offset: usize,
+    pub size: usize,
+    pub symbolize: fn(&mut String, usize) -> (usize, usize),
+    pub resolve: fn(&str) -> Option<(usize, usize)>,
+}
+
+impl FieldSymbol {
+    // Describe a field projected from a null object, e.g.
+    // FieldSymbol::new("value", field!(Pointer::<Self>::NULLPTR, value))
+    pub fn new<U: Symbolize>(name: &'static str, field: Pointer<U>) -> Self {
+        Self {
+            name,
+            offset: field.address as usize,
+            size: mem::size_of::<U>(),
+            symbolize: U::symbolize,
+            resolve: U::resolve,
+        }
+    }
+}
+
+// Guest type whose bytes can be traced back to field paths
+pub trait Symbolize: Sized {
+    // Named fields, none for values
+    fn fields() -> Vec<FieldSymbol> { vec![] }
+
+    // Extend path to the innermost field containing offset, returning that field's offset
+    // and size
+    fn symbolize(path: &mut String, offset: usize) -> (usize, usize) {
+        symbolize_in_fields::<Self>(path, offset)
+    }
+
+    // Offset and size of the field at a path like .name.buffer[5], the reverse of symbolize
+    fn resolve(path: &str) -> Option<(usize, usize)> { resolve_in_fields::<Self>(path) }
+}
+
+// Find offset in the fields of T. Padding between fields belongs to the object itself.
+pub fn symbolize_in_fields<T: Symbolize>(path: &mut String, offset: usize) -> (usize, usize) {
+    for field in T::fields() {
+        if (field.offset..field.offset + field.size).contains(&offset) {
+            let _ = write!(path, ".{}", field.name);
+            let (start, size) = (field.symbolize)(path, offset - field.offset);
+            return (field.offset + start, size);
+        }
+    }
+    (0, mem::size_of::<T>())
+}
+
+// Find the field path starts with in the fields of T
+pub fn resolve_in_fields<T: Symbolize>(path: &str) -> Option<(usize, usize)> {
+    if path.is_empty() { return Some((0, mem::size_of::<T>())); }
+
+    let path = path.strip_prefix('.')?;
+    let (name, rest) = path.split_at(path.find(['.', '[']).unwrap_or(path.len()));
+    let field = T::fields().into_iter().find(|field| field.name == name)?;
+    let (start, size) = (field.resolve)(rest)?;
+    Some((field.offset + start, size))
+}
+
+impl<T: Symbolize, const N: usize> Symbolize for [T; N] {
+    fn symbolize(path: &mut String, offset: usize) -> (usize, usize) {
+        let size = mem::size_of::<T>();
+        let index = offset / size;
+        let _ = write!(path, "[{}]", index);
+        let (start, len) = T::symbolize(path, offset % size);
+        (index * size + start, len)
+    }
+
+    fn resolve(path: &str) -> Option<(usize, usize)> {
+        if path.is_empty() { return Some((0, mem::size_of::<Self>())); }
+
+        let (index, rest) = path.strip_prefix('[')?.split_once(']')?;
+        let index: usize = index.parse().ok().filter(|&index| index < N)?;
+        let (start, size) = T::resolve(rest)?;
+        Some((index * mem::size_of::<T>() + start, size))
+    }
+}
+
+impl<T: GuestValue> Symbolize for Guest<T> {}
+
+impl<E> Symbolize for RawEnum<E> {}
+
+impl Symbolize for u8 {}
+
+// Implement Symbolize for a struct by naming its fields in order
+macro_rules! symbolize_fields {
+    ($name:ty { $($field:ident),* $(,)? }) => {
+        impl Symbolize for $name {
+            fn fields() -> Vec<FieldSymbol> {
+                let this = Pointer::<Self>::NULLPTR;
+                vec![$(FieldSymbol::new(stringify!($field), field!(this, $field))),*]
+            }
+        }
+    };
+}
+
+pub(crate) use symbolize_fields;
+
+// Field of an object traced from an address
+#[derive(Clone, Debug, PartialEq)]
+pub struct Symbol {
+    pub path: String,
+    // Start address and size of the field
+    pub address: u64,
+    pub size: usize,
+}
+
+// Trace address back to a field of the object at base, e.g. item_lists.buffer[37].value
+pub fn symbolize<T: Symbolize>(base: Pointer<T>, address: u64) -> Option<Symbol> {
+    let offset = address.checked_sub(base.address)? as usize;
+    if offset >= mem::size_of::<T>() { return None; }
+
+    let mut path = String::new();
+    let (start, size) = T::symbolize(&mut path, offset);
+
+    // Padding outside any field is named after the object
+    let path = match path.strip_prefix('.') {
+        Some(path) => path.to_string(),
+        None => format!("{}{}", any::type_name::<T>().rsplit("::").next().unwrap(), path),
+    };
+    Some(Symbol { path, address: base.address + start as u64, size })
+}
+
+// Find the address range of the field at path in the object at base, e.g. list_heads[3]
+pub fn resolve<T: Symbolize>(base: Pointer<T>, path: &str) -> Option<Range<u64>> {
+    let path = if path.starts_with('[') { path.to_string() } else { format!(".{}", path) };
+    let (start, size) = T::resolve(&path)?;
+    let start = base.address + start as u64;
+    Some(start..start + size as u64)
+}
+
+// Describe address as a field path, adding the offset into the field if it is not at its
+// start, e.g. item_lists.buffer[12].name.buffer[5] or list_heads[3]+0x4
+pub fn field_path<T: Symbolize>(base: Pointer<T>, address: u64) -> Option<String> {
+    let symbol = symbolize(base, address)?;
+    Some(if address == symbol.address { symbol.path } else {
+        format!("{}+0x{:x}", symbol.path, address - symbol.address)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pmdm::*;
+    use crate::types::*;
+
+    use super::*;
+
+    const PMDM: Pointer<PauseMenuDataMgr> = Pointer::<PauseMenuDataMgr>::NULLPTR;
+
+    #[test]
+    fn resolve_then_symbolize() {
+        for path in [
+            "item_lists.buffer[37].value",
+            "item_lists.buffer[12].name.buffer[5]",
+            "item_lists.buffer[4].ingredients.work.nodes[2].buffer[0]",
+            "item_lists.buffer[4].ingredients.work.ptrs[1]",
+            "item_lists.list1.start_end.next",
+            "list_heads[3]",
+            "newly_added_item.equipped",
+            "category_to_sort",
+        ] {
+            let range = resolve(PMDM, path).unwrap_or_else(|| panic!("{} does not resolve", path));
+            let symbol = symbolize(PMDM, range.start).unwrap();
+            assert_eq!(symbol.path, path);
+            assert_eq!(symbol.address..symbol.address + symbol.size as u64, range);
+        }
+    }
+
+    #[test]
+    fn symbolize_then_resolve() {
+        let type_name = any::type_name::<PauseMenuDataMgr>().rsplit("::").next().unwrap();
+        for address in 0..mem::size_of::<PauseMenuDataMgr>() as u64 {
+            let symbol = symbolize(PMDM, address).unwrap();
+            let range = symbol.address..symbol.address + symbol.size as u64;
+            assert!(range.contains(&address), "{} does not contain 0x{:x}", symbol.path, address);
+
+            // Padding between PMDM's own fields has no path to resolve
+            if symbol.path == type_name { continue; }
+            assert_eq!(resolve(PMDM, &symbol.path), Some(range), "{}", symbol.path);
+        }
+        assert_eq!(symbolize(PMDM, mem::size_of::<PauseMenuDataMgr>() as u64), None);
+    }
+
+    // Check that the named fields of T don't overlap and only leave room for padding, so no
+    // field was left out
+    fn check_fields_cover<T: Symbolize>() {
+        let mut fields = T::fields();
+        fields.sort_by_key(|field| field.offset);
+        let mut end = 0;
+        for field in &fields {
+            assert!(field.offset >= end, "{} overlaps the field before it", field.name);
+            // Padding is shorter than the alignment of the field after it
+            let align = 1 << field.offset.trailing_zeros().min(3);
+            assert_eq!(end.next_multiple_of(align), field.offset, "gap before {}", field.name);
+            end = field.offset + field.size;
+        }
+        let gap = mem::size_of::<T>() - end;
+        assert!(gap < mem::align_of::<T>(), "gap at the end of {}", any::type_name::<T>());
+    }
+
+    #[test]
+    fn fields_cover_structs() {
+        check_fields_cover::<PauseMenuDataMgr>();
+        check_fields_cover::<Lists>();
+        check_fields_cover::<OffsetList<PouchItem>>();
+        check_fields_cover::<PouchItem>();
+        check_fields_cover::<ListNode>();
+        check_fields_cover::<FixedSafeString<64>>();
+        check_fields_cover::<FreeList>();
+        check_fields_cover::<CriticalSection>();
+        check_fields_cover::<GrabbedItemInfo>();
+    }
+}
@@ -1,9 +1,13 @@
+use std::fmt::Write;
 use std::marker::PhantomData;
 use std::mem;
 
+use serde::Deserialize;
 use serde_json;
 
+use crate::error::*;
 use crate::mem::*;
+use crate::symbol::*;
 use crate::traits::*;
 
 pub const NUM_POUCH_ITEMS_MAX: i32 = 420;
@@ -13,57 +17,102 @@ pub const NUM_POUCH_CATEGORIES: i32 = 7;
 pub const NUM_TAB_MAX: i32 = 50;
 pub const NUM_GRABBABLE_ITEMS: i32 = 5;
 
-#[derive(Clone, Copy, Default, PartialEq)]
-#[repr(i32)]
-pub enum PouchItemType {
-    Sword,
-    Bow,
-    Arrow,
-    Shield,
-    ArmorHead,
-    ArmorUpper,
-    ArmorLower,
-    Material,
-    Food,
-    KeyItem,
-    #[default] Invalid,
-}
-
-impl Updatable for PouchItemType {}
+// Define an enum the guest stores as an i32, adding an Unknown variant for values a glitched
+// slot can leave out of range
+macro_rules! guest_enum {
+    (
+        $(#[$meta:meta])* $vis:vis enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            Unknown(i32),
+        }
 
-#[derive(Clone, Copy, Default, PartialEq)]
-#[repr(i32)]
-pub enum PouchCategory {
-    Sword,
-    Bow,
-    Shield,
-    Armor,
-    Material,
-    Food,
-    KeyItem,
-    #[default] Invalid,
+        impl TryFrom<i32> for $name {
+            type Error = i32;
+
+            fn try_from(value: i32) -> Result<Self, i32> {
+                match value { $($value => Ok(Self::$variant),)* _ => Err(value) }
+            }
+        }
+
+        impl GuestEnum for $name {
+            fn raw(self) -> i32 {
+                match self { $(Self::$variant => $value,)* Self::Unknown(value) => value }
+            }
+
+            fn unknown(value: i32) -> Self { Self::Unknown(value) }
+        }
+    };
+}
+
+guest_enum! {
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+    pub enum PouchItemType {
+        Sword = 0,
+        Bow = 1,
+        Arrow = 2,
+        Shield = 3,
+        ArmorHead = 4,
+        ArmorUpper = 5,
+        ArmorLower = 6,
+        Material = 7,
+        Food = 8,
+        KeyItem = 9,
+        #[default] Invalid = 10,
+    }
 }
 
-impl Updatable for PouchCategory {}
+impl PouchItemType {
+    // Pouch category the type is sorted and displayed under
+    pub fn category(&self) -> PouchCategory {
+        match self {
+            Self::Sword => PouchCategory::Sword,
+            Self::Bow | Self::Arrow => PouchCategory::Bow,
+            Self::Shield => PouchCategory::Shield,
+            Self::ArmorHead | Self::ArmorUpper | Self::ArmorLower => PouchCategory::Armor,
+            Self::Material => PouchCategory::Material,
+            Self::Food => PouchCategory::Food,
+            Self::KeyItem => PouchCategory::KeyItem,
+            Self::Invalid | Self::Unknown(_) => PouchCategory::Invalid,
+        }
+    }
+}
 
-#[derive(Clone, Copy, Default, PartialEq)]
-#[repr(i32)]
-pub enum ItemUse {
-    WeaponSmallSword,
-    WeaponLargeSword,
-    WeaponSpear,
-    WeaponBow,
-    WeaponShield,
-    ArmorHead,
-    ArmorLower,
-    ArmorUpper,
-    Item,
-    ImportantItem,
-    CureItem,
-    #[default] Invalid,
-}
-
-impl Updatable for ItemUse {}
+guest_enum! {
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub enum PouchCategory {
+        Sword = 0,
+        Bow = 1,
+        Shield = 2,
+        Armor = 3,
+        Material = 4,
+        Food = 5,
+        KeyItem = 6,
+        #[default] Invalid = 7,
+    }
+}
+
+guest_enum! {
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub enum ItemUse {
+        WeaponSmallSword = 0,
+        WeaponLargeSword = 1,
+        WeaponSpear = 2,
+        WeaponBow = 3,
+        WeaponShield = 4,
+        ArmorHead = 5,
+        ArmorLower = 6,
+        ArmorUpper = 7,
+        Item = 8,
+        ImportantItem = 9,
+        CureItem = 10,
+        #[default] Invalid = 11,
+    }
+}
 
 #[derive(Clone, Copy, Default, PartialEq)]
 #[repr(u32)]
@@ -81,95 +130,159 @@ pub enum WeaponModifier {
     IsYellow = 0x80000000,
 }
 
-impl Updatable for WeaponModifier {}
-
-#[derive(Clone, Copy, Default, PartialEq)]
-#[repr(i32)]
-pub enum CookEffectId {
-    #[default] None = -1,
-    LifeRecover = 1,
-    LifeMaxUp,
-    ResistHot = 4,
-    ResistCold,
-    ResistElectric,
-    AttackUp = 10,
-    DefenseUp,
-    Quietness,
-    MovingSpeed,
-    GutsRecover,
-    ExGutsMaxUp,
-    Fireproof,
-}
-
-impl Updatable for CookEffectId {}
+guest_enum! {
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub enum CookEffectId {
+        #[default] None = -1,
+        LifeRecover = 1,
+        LifeMaxUp = 2,
+        ResistHot = 4,
+        ResistCold = 5,
+        ResistElectric = 6,
+        AttackUp = 10,
+        DefenseUp = 11,
+        Quietness = 12,
+        MovingSpeed = 13,
+        GutsRecover = 14,
+        ExGutsMaxUp = 15,
+        Fireproof = 16,
+    }
+}
 
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct ListNode {
-    pub prev: Pointer<Self>,
-    pub next: Pointer<Self>,
+    pub prev: Guest<Pointer<Self>>,
+    pub next: Guest<Pointer<Self>>,
 }
 
-impl Updatable for ListNode {}
+impl ListNode {
+    // Unlink node from its neighbours
+    pub fn erase(self: Pointer<Self>, memory: &mut Memory) -> Result<(), SimError> {
+        let prev = field!(self, prev).get(memory)?;
+        let next = field!(self, next).get(memory)?;
+        if prev != Pointer::NULLPTR { field!(prev, next).set(memory, next)?; }
+        if next != Pointer::NULLPTR { field!(next, prev).set(memory, prev)?; }
+
+        self.write(memory, Box::new(Self::default()))?;
+        Ok(())
+    }
+
+    // Link node behind this node
+    pub fn insert_back(
+        self: Pointer<Self>, memory: &mut Memory, node: Pointer<Self>
+    ) -> Result<(), SimError> {
+        let next = field!(self, next).get(memory)?;
+
+        field!(self, next).set(memory, node)?;
+        field!(node, prev).set(memory, self)?;
+        field!(node, next).set(memory, next)?;
+        if next != Pointer::NULLPTR { field!(next, prev).set(memory, node)?; }
+        Ok(())
+    }
+
+    // Link node in front of this node
+    pub fn insert_front(
+        self: Pointer<Self>, memory: &mut Memory, node: Pointer<Self>
+    ) -> Result<(), SimError> {
+        let prev = field!(self, prev).get(memory)?;
+
+        field!(self, prev).set(memory, node)?;
+        field!(node, prev).set(memory, prev)?;
+        field!(node, next).set(memory, self)?;
+        if prev != Pointer::NULLPTR { field!(prev, next).set(memory, node)?; }
+        Ok(())
+    }
+}
+
+symbolize_fields!(ListNode { prev, next });
 
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct FixedSafeStringVTable {
-    pub super_dtor: Pointer,
-    pub super_assure_termination_impl: Pointer,
-    pub dtor: Pointer,
-    pub assure_termination_impl: Pointer,
+    pub super_dtor: Guest<Pointer>,
+    pub super_assure_termination_impl: Guest<Pointer>,
+    pub dtor: Guest<Pointer>,
+    pub assure_termination_impl: Guest<Pointer>,
 }
 
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct FixedSafeString<const L: usize> {
-    pub vptr: Pointer<FixedSafeStringVTable>,
-    pub string_top: Pointer<i8>,
-    pub buffer_size: i32,
+    pub vptr: Guest<Pointer<FixedSafeStringVTable>>,
+    pub string_top: Guest<Pointer<i8>>,
+    pub buffer_size: Guest<i32>,
     pub buffer: [u8; L],
 }
 
 impl<const L: usize> FixedSafeString<L> {
-    pub fn assure_termination_impl(&mut self, memory: &mut Memory, this: Pointer<Self>) {
-        **(self.vptr.cast::<Pointer>().to_ne() + mem::offset_of!(
-            FixedSafeStringVTable, assure_termination_impl
-        ) as u64).read(memory).unwrap();
-        (self.string_top.to_ne() + i32::from_le(self.buffer_size) as u64 - 1).write(
-            memory, Box::new(Default::default())
-        ).unwrap();
-        self.update(memory, &this);
+    pub fn assure_termination_impl(
+        self: Pointer<Self>, memory: &mut Memory
+    ) -> Result<(), SimError> {
+        let vptr = field!(self, vptr).get(memory)?;
+        field!(vptr, assure_termination_impl).get(memory)?.call(memory, self.address)?;
+
+        let string_top = field!(self, string_top).get(memory)?;
+        let buffer_size = field!(self, buffer_size).get(memory)?;
+        (string_top + buffer_size as u64 - 1).set(memory, i8::default())?;
+        Ok(())
     }
 
-    pub fn is_equal(&mut self, memory: &mut Memory, this: Pointer<Self>, other: Self) -> bool {
-        self.assure_termination_impl(memory, this);
-        if self.string_top == other.string_top { return true; }
+    pub fn is_equal(
+        self: Pointer<Self>, memory: &mut Memory, other: Pointer<Self>
+    ) -> Result<bool, SimError> {
+        self.assure_termination_impl(memory)?;
+        let string_top = field!(self, string_top).get(memory)?;
+        let other_string_top = field!(other, string_top).get(memory)?;
+        if string_top == other_string_top { return Ok(true); }
 
         for i in 0..=0x80000 {
-            let current = *(self.string_top.to_ne() + i).read(memory).unwrap();
+            let current = (string_top + i).get(memory)?;
 
-            if current != *(other.string_top.to_ne() + i).read(memory).unwrap() { return false; }
-            if current == i8::default() { return true; }
+            if current != (other_string_top + i).get(memory)? {
+                return Ok(false);
+            }
+            if current == i8::default() { return Ok(true); }
         }
 
-        false
+        Ok(false)
     }
 
-    pub fn is_equal_str(&mut self, memory: &mut Memory, this: Pointer<Self>, other: &str) -> bool {
-        self.assure_termination_impl(memory, this);
+    pub fn is_equal_str(
+        self: Pointer<Self>, memory: &mut Memory, other: &str
+    ) -> Result<bool, SimError> {
+        self.assure_termination_impl(memory)?;
+        let string_top = field!(self, string_top).get(memory)?;
 
         for i in 0..=0x80000 {
-            let current = *(self.string_top.to_ne() + i).read(memory).unwrap();
+            let current = (string_top + i).get(memory)?;
 
-            if current != other.as_bytes()[i as usize] as i8 { return false; }
-            if current == i8::default() { return true; }
+            if current != other.as_bytes().get(i as usize).map_or(0, |&x| x as i8) {
+                return Ok(false);
+            }
+            if current == i8::default() { return Ok(true); }
         }
 
-        false
+        Ok(false)
+    }
+
+    pub fn copy_str(
+        self: Pointer<Self>, memory: &mut Memory, other: &str
+    ) -> Result<(), SimError> {
+        let string_top = field!(self, string_top).get(memory)?;
+        let buffer_size = field!(self, buffer_size).get(memory)?;
+        let length = other.len().min(buffer_size as usize - 1);
+
+        for (i, byte) in other.bytes().take(length).enumerate() {
+            (string_top + i as u64).set(memory, byte as i8)?;
+        }
+        (string_top + length as u64).set(memory, i8::default())?;
+        Ok(())
     }
 
-    pub fn clear(&mut self, memory: &mut Memory) {
-        self.string_top.to_ne().write(memory, Box::new(Default::default())).unwrap();
+    pub fn clear(self: Pointer<Self>, memory: &mut Memory) -> Result<(), SimError> {
+        field!(self, string_top).get(memory)?.set(memory, i8::default())?;
+        Ok(())
     }
 }
 
@@ -180,52 +293,48 @@ impl<const L: usize> ToString for FixedSafeString<L> {
             .take_while(|&&x| x != 0)
             .cloned()
             .collect();
-        String::from_utf8(trimmed_string).unwrap()
+        String::from_utf8_lossy(&trimmed_string).into_owned()
     }
 }
 
-impl<const L: usize> Updatable for FixedSafeString<L> {}
+impl<const L: usize> Constructor for FixedSafeString<L> {
+    fn ctor(self: Pointer<Self>, memory: &mut Memory) -> Result<(), SimError> {
+        field!(self, string_top).set(memory, field!(self, buffer).cast())?;
+        field!(self, buffer_size).set(memory, L as i32)?;
+        self.assure_termination_impl(memory)?;
 
-impl<const L: usize> Constructor for FixedSafeString<L> where [(); mem::size_of::<Self>()]: {
-    fn ctor(&mut self, memory: &mut Memory, this: Pointer<Self>) {
-        (this.cast() + mem::offset_of!(Self, string_top) as u64).write(memory, Box::new((
-            this.cast::<i8>() + mem::offset_of!(Self, buffer) as u64
-        ).to_le())).unwrap();
-        self.update(memory, &this);
-
-        (this.cast() + mem::offset_of!(Self, buffer_size) as u64).write(memory, Box::new((
-            L as i32
-        ).to_le())).unwrap();
-        self.update(memory, &this);
-        self.assure_termination_impl(memory, this);
-
-        self.string_top.to_ne().write(memory, Box::new(Default::default())).unwrap();
-        self.update(memory, &this);
+        field!(self, string_top).get(memory)?.set(memory, i8::default())?;
+        Ok(())
     }
 }
 
+impl<const L: usize> Symbolize for FixedSafeString<L> {
+    fn fields() -> Vec<FieldSymbol> { vec![
+        FieldSymbol::new("vptr", field!(Pointer::<Self>::NULLPTR, vptr)),
+        FieldSymbol::new("string_top", field!(Pointer::<Self>::NULLPTR, string_top)),
+        FieldSymbol::new("buffer_size", field!(Pointer::<Self>::NULLPTR, buffer_size)),
+        FieldSymbol::new("buffer", field!(Pointer::<Self>::NULLPTR, buffer)),
+    ] }
+}
+
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct CookData {
-    pub health_recover: i32,
-    pub effect_duration: i32,
-    pub sell_price: i32,
-    pub effect_id: f32,
-    pub effect_level: f32,
+    pub health_recover: Guest<i32>,
+    pub effect_duration: Guest<i32>,
+    pub sell_price: Guest<i32>,
+    pub effect_id: Guest<f32>,
+    pub effect_level: Guest<f32>,
 }
 
-impl Updatable for CookData {}
-
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct WeaponData {
-    pub modifier_value: u32,
-    unused: u32,
-    pub modifier: u32,
+    pub modifier_value: Guest<u32>,
+    unused: Guest<u32>,
+    pub modifier: Guest<u32>,
 }
 
-impl Updatable for WeaponData {}
-
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub union Data {
@@ -235,38 +344,35 @@ pub union Data {
 
 impl Default for Data { fn default() -> Self { Self { cook: Default::default() } } }
 
-impl Updatable for Data {}
+// Whether data holds cook or weapon data depends on the item type, so it is not split up
+impl Symbolize for Data {}
 
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct FreeListNode {
-    pub next_free: Pointer<Self>,
+    pub next_free: Guest<Pointer<Self>>,
 }
 
-impl Updatable for FreeListNode {}
-
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct FreeList {
-    pub free: Pointer<FreeListNode>,
-    pub work: Pointer,
+    pub free: Guest<Pointer<FreeListNode>>,
+    pub work: Guest<Pointer>,
 }
 
-impl Updatable for FreeList {}
+symbolize_fields!(FreeList { free, work });
 
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct ObjArrayWorkNode<T> {
     pub item: T,
-    pub pointer: Pointer<T>,
+    pub pointer: Guest<Pointer<T>>,
 }
 
-impl<T> Updatable for ObjArrayWorkNode<T> {}
-
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub union ObjArrayNode<T> where T: Copy {
-    pub next_node: Pointer<Self>,
+    pub next_node: Guest<Pointer<Self>>,
     pub item: T,
 }
 
@@ -277,264 +383,388 @@ impl<T> Default for ObjArrayNode<T> where T: Copy {
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct FixedObjArray<T, const N: i32> where [(); N as usize]: {
-    pub ptr_num: i32,
-    pub ptr_num_max: i32,
-    pub ptrs: Pointer<Pointer<T>>,
+    pub ptr_num: Guest<i32>,
+    pub ptr_num_max: Guest<i32>,
+    pub ptrs: Guest<Pointer<Guest<Pointer<T>>>>,
     pub free_list: FreeList,
     pub work: [ObjArrayWorkNode<T>; N as usize],
 }
 
-impl<T, const N: i32> Updatable for FixedObjArray<T, N> where [(); N as usize]: {}
-
-impl<T, const N: i32> Constructor for FixedObjArray<T, N> where [(); N as usize]:, T: Copy {
-    fn ctor(&mut self, memory: &mut Memory, this: Pointer<Self>) {
-        let element_size = mem::size_of::<ObjArrayNode<T>>() as i32;
+impl<T: Symbolize + Copy, const N: i32> Symbolize for FixedObjArray<T, N>
+where [(); N as usize]: {
+    fn fields() -> Vec<FieldSymbol> { vec![
+        FieldSymbol::new("ptr_num", field!(Pointer::<Self>::NULLPTR, ptr_num)),
+        FieldSymbol::new("ptr_num_max", field!(Pointer::<Self>::NULLPTR, ptr_num_max)),
+        FieldSymbol::new("ptrs", field!(Pointer::<Self>::NULLPTR, ptrs)),
+        FieldSymbol::new("free_list", field!(Pointer::<Self>::NULLPTR, free_list)),
+    ] }
+
+    // Work holds the object nodes, followed by the pointers ptrs points at
+    fn symbolize(path: &mut String, offset: usize) -> (usize, usize) {
+        let work = mem::offset_of!(Self, work);
+        let node_size = mem::size_of::<ObjArrayNode<T>>();
+        let ptr_size = mem::size_of::<Guest<Pointer<T>>>();
+        let nodes_size = N as usize * node_size;
+        let Some(offset_in_work) = offset.checked_sub(work).filter(
+            |&offset_in_work| offset_in_work < nodes_size + N as usize * ptr_size
+        ) else { return symbolize_in_fields::<Self>(path, offset); };
+
+        if offset_in_work < nodes_size {
+            let index = offset_in_work / node_size;
+            let _ = write!(path, ".work.nodes[{}]", index);
+            let (start, size) = T::symbolize(path, offset_in_work % node_size);
+            (work + index * node_size + start, size)
+        } else {
+            let index = (offset_in_work - nodes_size) / ptr_size;
+            let _ = write!(path, ".work.ptrs[{}]", index);
+            (work + nodes_size + index * ptr_size, ptr_size)
+        }
+    }
 
-        let idx_multiplier = element_size / 0x8;
-        (this.cast() + mem::offset_of!(Self, work) as u64).write(memory, unsafe { Box::from_raw(
-            vec![u64::default(); (N * idx_multiplier) as usize].as_mut_ptr()
-        ) }).unwrap();
-        self.update(memory, &this);
-        let ptrs = this.cast::<Pointer>() + mem::offset_of!(Self, work) as u64;
+    fn resolve(path: &str) -> Option<(usize, usize)> {
+        let work = mem::offset_of!(Self, work);
+        let node_size = mem::size_of::<ObjArrayNode<T>>();
+        let ptr_size = mem::size_of::<Guest<Pointer<T>>>();
+        let Some(path) = path.strip_prefix(".work.") else {
+            return resolve_in_fields::<Self>(path);
+        };
+
+        let (array, path) = path.split_at(path.find('[')?);
+        let (index, rest) = path.strip_prefix('[')?.split_once(']')?;
+        let index: usize = index.parse().ok().filter(|&index| index < N as usize)?;
+        match array {
+            "nodes" => {
+                let (start, size) = T::resolve(rest)?;
+                Some((work + index * node_size + start, size))
+            },
+            "ptrs" if rest.is_empty() => {
+                Some((work + N as usize * node_size + index * ptr_size, ptr_size))
+            },
+            _ => None,
+        }
+    }
+}
 
-        (this.cast() + mem::offset_of!(Self, work) as u64).write(memory, Box::new(
-            FreeListNode::default()
-        )).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, free_list.free) as u64).write(memory, Box::new((
-            this.cast::<FreeListNode>() + mem::offset_of!(Self, work) as u64
-        ).to_le())).unwrap();
-        self.update(memory, &this);
-
-        for i in 0..N - 1 {
-            let next_free = ptrs.cast::<FreeListNode>() + ((i + 1) * idx_multiplier) as u64;
-            next_free.write(memory, Box::new(Default::default())).unwrap();
-            self.update(memory, &this);
-            (ptrs.cast() + (i * idx_multiplier) as u64).write(memory, Box::new(FreeListNode {
-                next_free: next_free.to_le()
-            })).unwrap();
-            self.update(memory, &this);
+impl<T, const N: i32> Constructor for FixedObjArray<T, N> where [(); N as usize]:, T: Copy {
+    fn ctor(self: Pointer<Self>, memory: &mut Memory) -> Result<(), SimError> {
+        let element_size = mem::size_of::<ObjArrayNode<T>>() as u64;
+
+        let work = field!(self, work);
+        work.write(memory, Box::new(unsafe {
+            mem::zeroed::<[ObjArrayWorkNode<T>; N as usize]>()
+        }))?;
+        let nodes = work.cast::<FreeListNode>();
+
+        nodes.write(memory, Box::new(FreeListNode::default()))?;
+        field!(self, free_list.free).set(memory, nodes)?;
+
+        for i in 0..N as u64 - 1 {
+            let next_free = nodes + (i + 1) * element_size;
+            next_free.write(memory, Box::new(Default::default()))?;
+            field!(nodes + i * element_size, next_free).set(memory, next_free)?;
         }
 
-        (ptrs.cast() + ((N - 1) * idx_multiplier) as u64).write(memory, Box::new(
+        (nodes + (N as u64 - 1) * element_size).write(memory, Box::new(
             FreeListNode::default()
-        )).unwrap();
-        self.update(memory, &this);
+        ))?;
 
-        (this.cast() + mem::offset_of!(Self, free_list.work) as u64).write(memory, Box::new((
-            this.cast::<u8>() + mem::offset_of!(Self, work) as u64
-        ).to_le())).unwrap();
-        self.update(memory, &this);
+        field!(self, free_list.work).set(memory, work.cast())?;
 
-        (this.cast() + mem::offset_of!(Self, ptrs) as u64).write(memory, Box::new((
-            this.cast::<Pointer<T>>() + mem::offset_of!(Self, work) as u64 + element_size as u64
-        ).to_le())).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, ptr_num) as u64).write(memory, Box::new(
-            i32::default()
-        )).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, ptr_num_max) as u64).write(memory, Box::new(
-            N.to_le()
-        )).unwrap();
-        self.update(memory, &this);
+        field!(self, ptrs).set(memory, (work.cast::<u8>() + N as u64 * element_size).cast())?;
+        field!(self, ptr_num).set(memory, i32::default())?;
+        field!(self, ptr_num_max).set(memory, N)?;
+        Ok(())
     }
 }
 
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct PouchItem {
-    pub vptr: Pointer<Pointer>,
+    pub vptr: Guest<Pointer<Pointer>>,
     pub list_node: ListNode,
-    pub item_type: PouchItemType,
-    pub item_use: ItemUse,
-    pub value: i32,
-    pub equipped: bool,
-    pub in_inventory: bool,
+    pub item_type: RawEnum<PouchItemType>,
+    pub item_use: RawEnum<ItemUse>,
+    pub value: Guest<i32>,
+    pub equipped: Guest<bool>,
+    pub in_inventory: Guest<bool>,
     pub name: FixedSafeString<64>,
     pub data: Data,
     pub ingredients: FixedObjArray<FixedSafeString<64>, NUM_INGREDIENTS_MAX>,
 }
 
-impl Updatable for PouchItem {}
-
 impl Constructor for PouchItem {
-    fn ctor(&mut self, memory: &mut Memory, this: Pointer<Self>) {
-        (this.cast() + mem::offset_of!(Self, list_node) as u64).write(memory, Box::new(
-            ListNode::default()
-        )).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, item_type) as u64).write(memory, Box::new((
-            PouchItemType::default() as i32
-        ).to_le())).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, item_use) as u64).write(memory, Box::new((
-            ItemUse::default() as i32
-        ).to_le())).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, value) as u64).write(memory, Box::new(
-            i32::default()
-        )).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, equipped) as u64).write(memory, Box::new(
-            bool::default()
-        )).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, in_inventory) as u64).write(
-            memory, Box::new(true)
-        ).unwrap();
-        self.update(memory, &this);
-        self.name.ctor(memory, this.cast() + mem::offset_of!(Self, name) as u64);
-        (this.cast() + mem::offset_of!(Self, data) as u64).write(memory, Box::new(
-            Data::default()
-        )).unwrap();
-        self.update(memory, &this);
-        self.ingredients.ctor(memory, this.cast() + mem::offset_of!(Self, ingredients) as u64);
-        (this.cast() + mem::offset_of!(Self, data.cook.effect_id) as u64).write(memory, Box::new((
-            CookEffectId::default() as i32 as f32
-        ).to_le_bytes())).unwrap();
-        self.update(memory, &this);
-        (this.cast() + mem::offset_of!(Self, data.cook.effect_level) as u64).write(
-            memory, Box::new(f32::default().to_le_bytes())
-        ).unwrap();
-        self.update(memory, &this);
-
+    fn ctor(self: Pointer<Self>, memory: &mut Memory) -> Result<(), SimError> {
+        field!(self, list_node).write(memory, Box::new(ListNode::default()))?;
+        field!(self, item_type).set(memory, PouchItemType::default())?;
+        field!(self, item_use).set(memory, ItemUse::default())?;
+        field!(self, value).set(memory, i32::default())?;
+        field!(self, equipped).set(memory, bool::default())?;
+        field!(self, in_inventory).set(memory, true)?;
+        field!(self, name).ctor(memory)?;
+        field!(self, data).write(memory, Box::new(Data::default()))?;
+        field!(self, ingredients).ctor(memory)?;
+        field!(self, data.cook.effect_id).set(
+            memory, CookEffectId::default().raw() as f32
+        )?;
+        field!(self, data.cook.effect_level).set(memory, f32::default())?;
+
+        let ingredients = field!(self, ingredients);
         for _ in 0..NUM_INGREDIENTS_MAX {
-            let ptr = self.ingredients.free_list.free.cast::<FixedSafeString<64>>().to_ne();
+            let free = field!(ingredients, free_list.free);
+            let ptr = free.get(memory)?;
             if ptr != Pointer::NULLPTR {
-                (this.cast() + mem::offset_of!(Self, ingredients.free_list.free) as u64).write(
-                    memory, ptr.cast::<Pointer<FreeListNode>>().read(memory).unwrap()
-                ).unwrap();
-                self.update(memory, &this);
+                free.set(memory, field!(ptr, next_free).get(memory)?)?;
             }
 
-            ptr.read(memory).unwrap().ctor(memory, ptr);
-            self.update(memory, &this);
-
-            (this.cast() + mem::offset_of!(Self, ingredients.ptrs) as u64 + (i32::from_le(
-                self.ingredients.ptr_num
-            ) * 0x8) as u64).write(memory, Box::new(ptr.to_le())).unwrap();
-            self.update(memory, &this);
-            (this.cast() + mem::offset_of!(Self, ingredients.ptr_num) as u64).write(
-                memory, Box::new((i32::from_le(self.ingredients.ptr_num) + 1).to_le())
-            ).unwrap();
-            self.update(memory, &this);
+            // Placement new sets the same vtable as the name's
+            let ptr = ptr.cast::<FixedSafeString<64>>();
+            field!(ptr, vptr).set(memory, field!(self, name.vptr).get(memory)?)?;
+            ptr.ctor(memory)?;
+
+            let ptrs = field!(ingredients, ptrs).get(memory)?;
+            let ptr_num = field!(ingredients, ptr_num).get(memory)?;
+            let ptr_size = mem::size_of::<Guest<Pointer>>() as u64;
+            (ptrs + ptr_num as u64 * ptr_size).set(memory, ptr)?;
+            field!(ingredients, ptr_num).set(memory, ptr_num + 1)?;
         }
+        Ok(())
     }
 }
 
-pub fn translate_name(actor_name: &str, lang_data: serde_json::Value) -> Option<String> {
+pub fn translate_name(actor_name: &str, lang_data: &serde_json::Value) -> Option<String> {
     lang_data.get(actor_name)?.as_str().map(String::from)
 }
 
+symbolize_fields!(PouchItem {
+    vptr, list_node, item_type, item_use, value, equipped, in_inventory, name, data, ingredients
+});
+
+// nn::os::MutexType
+#[cfg(not(feature = "wiiu"))]
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct MutexType {
     pub state: u8,
-    pub is_recursive: bool,
-    pub lock_level: i32,
-    pub nest_count: i32,
-    pub owner_thread: Pointer,
-    pub mutex: i32,
+    pub is_recursive: Guest<bool>,
+    pub lock_level: Guest<i32>,
+    pub nest_count: Guest<i32>,
+    pub owner_thread: Guest<Pointer>,
+    pub mutex: Guest<i32>,
+}
+
+// OSMutex
+#[cfg(feature = "wiiu")]
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct MutexType {
+    pub tag: Guest<u32>,
+    pub name: Guest<Pointer<i8>>,
+    _8: Guest<u32>,
+    pub queue: [Guest<Pointer>; 0x4],
+    pub owner_thread: Guest<Pointer>,
+    pub count: Guest<i32>,
+    pub link: ListNode,
 }
 
-impl Updatable for MutexType {}
+// Layout differs between platforms, so the mutex is not split up
+impl Symbolize for MutexType {}
 
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct CriticalSection {
     // IDisposer
-    pub vptr: Pointer<Pointer>,
-    pub disposer_heap: Pointer,
+    pub vptr: Guest<Pointer<Pointer>>,
+    pub disposer_heap: Guest<Pointer>,
     pub list_node: ListNode,
 
     // CriticalSection
     pub critical_section_inner: MutexType,
 }
 
-impl Updatable for CriticalSection {}
+symbolize_fields!(CriticalSection { vptr, disposer_heap, list_node, critical_section_inner });
 
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct OffsetList<T> {
     // ListImpl
     pub start_end: ListNode,
-    pub count: i32,
+    pub count: Guest<i32>,
 
     // OffsetList
-    pub offset: i32,
+    pub offset: Guest<i32>,
 
     phantom: PhantomData<T>,
 }
 
 impl<T> OffsetList<T> {
     fn obj_to_list_node(&self, obj: Pointer<T>) -> Pointer<ListNode> {
-        (obj + self.offset as u64).cast()
+        // Pointer math wraps like the guest's, so negative offsets and stray nodes are fine
+        (obj + self.offset.get() as u64).cast()
     }
 
-    fn list_node_to_obj(&self, node: Pointer<ListNode>) -> Pointer<T> {
-        (node - self.offset as u64).cast()
+    pub fn list_node_to_obj(&self, node: Pointer<ListNode>) -> Pointer<T> {
+        (node - self.offset.get() as u64).cast()
     }
 
     fn list_node_to_obj_with_null_check(&self, node: Pointer<ListNode>) -> Pointer<T> {
         if node == Pointer::NULLPTR { Pointer::NULLPTR } else { self.list_node_to_obj(node) }
     }
 
-    pub fn erase(&mut self, memory: &mut Memory, this: Pointer<Self>, item: Pointer<T>) where [
-        (); mem::size_of::<Self>()
-    ]: {
-        let node_ptr = (item + i32::from_le(self.offset) as u64).cast::<ListNode>();
-        let node = node_ptr.read(memory).unwrap();
+    pub fn erase(
+        self: Pointer<Self>, memory: &mut Memory, item: Pointer<T>
+    ) -> Result<(), SimError> {
+        self.view(memory)?.obj_to_list_node(item).erase(memory)?;
 
-        if node.prev != Pointer::NULLPTR {
-            (node.prev.to_ne() + mem::offset_of!(ListNode, next) as u64).write(
-                memory, node.next.to_ne().read(memory).unwrap()
-            ).unwrap();
-            self.update(memory, &this);
-        }
-        if node.next != Pointer::NULLPTR {
-            (node.next.to_ne() + mem::offset_of!(ListNode, prev) as u64).write(
-                memory, node.prev.to_ne().read(memory).unwrap()
-            ).unwrap();
-            self.update(memory, &this);
-        }
+        let count = field!(self, count);
+        count.set(memory, count.get(memory)?.wrapping_sub(1))?;
+        Ok(())
+    }
+
+    pub fn push_back(
+        self: Pointer<Self>, memory: &mut Memory, obj: Pointer<T>
+    ) -> Result<(), SimError> {
+        let node = self.view(memory)?.obj_to_list_node(obj);
+        field!(self, start_end).insert_front(memory, node)?;
+
+        let count = field!(self, count);
+        count.set(memory, count.get(memory)?.wrapping_add(1))?;
+        Ok(())
+    }
+
+    pub fn push_front(
+        self: Pointer<Self>, memory: &mut Memory, obj: Pointer<T>
+    ) -> Result<(), SimError> {
+        let node = self.view(memory)?.obj_to_list_node(obj);
+        field!(self, start_end).insert_back(memory, node)?;
 
-        (node_ptr.cast() + mem::offset_of!(ListNode, prev) as u64).write(memory, Box::new(
-            ListNode::default()
-        )).unwrap();
-        self.update(memory, &this);
+        let count = field!(self, count);
+        count.set(memory, count.get(memory)?.wrapping_add(1))?;
+        Ok(())
+    }
 
-        (this.cast() + mem::offset_of!(Self, count) as u64).write(memory, Box::new((i32::from_le(
-            self.count
-        ) - 1).to_le())).unwrap();
-        self.update(memory, &this);
+    pub fn pop_front(self: Pointer<Self>, memory: &mut Memory) -> Result<Pointer<T>, SimError> {
+        let obj = self.view(memory)?.front();
+        if obj != Pointer::NULLPTR { self.erase(memory, obj)?; }
+        Ok(obj)
     }
 
-    pub fn prev(&self, memory: &Memory, this: Pointer<Self>, obj: Pointer<T>) -> Pointer<T> {
-        let prev_node = self.obj_to_list_node(obj).read(memory).unwrap().prev.to_ne();
-        if prev_node == this.cast() + mem::offset_of!(Self, start_end) as u64 { Pointer::NULLPTR }
-        else { self.list_node_to_obj(prev_node) }
+    pub fn front(&self) -> Pointer<T> {
+        if self.count.get() <= 0 { return Pointer::NULLPTR; }
+        self.list_node_to_obj(self.start_end.next.get())
     }
 
-    pub fn next(&self, memory: &Memory, this: Pointer<Self>, obj: Pointer<T>) -> Pointer<T> {
-        let next_node = self.obj_to_list_node(obj).read(memory).unwrap().next.to_ne();
-        if next_node == this.cast() + mem::offset_of!(Self, start_end) as u64 { Pointer::NULLPTR }
-        else { self.list_node_to_obj(next_node) }
+    pub fn prev(
+        self: Pointer<Self>, memory: &Memory, obj: Pointer<T>
+    ) -> Result<Pointer<T>, SimError> {
+        let list = self.view(memory)?;
+        let prev_node = list.obj_to_list_node(obj).view(memory)?.prev.get();
+        Ok(if prev_node == field!(self, start_end) {
+            Pointer::NULLPTR
+        } else { list.list_node_to_obj(prev_node) })
     }
 
-    pub fn nth(&self, memory: &Memory, n: i32) -> Pointer<T> {
-        if i32::from_le(self.count) as u32 <= n as u32 { return Pointer::new(0u64); }
-        let mut node = self.start_end.next.to_ne();
-        for _ in 0..n { node = node.read(memory).unwrap().next.to_ne(); }
-        self.list_node_to_obj_with_null_check(node)
+    pub fn next(
+        self: Pointer<Self>, memory: &Memory, obj: Pointer<T>
+    ) -> Result<Pointer<T>, SimError> {
+        let list = self.view(memory)?;
+        let next_node = list.obj_to_list_node(obj).view(memory)?.next.get();
+        Ok(if next_node == field!(self, start_end) {
+            Pointer::NULLPTR
+        } else { list.list_node_to_obj(next_node) })
     }
 
-    pub fn sort(&mut self, memory: &mut Memory, this: Pointer<Self>, cmp: fn(
-        &Memory, Pointer<T>, Pointer<T>
-    ) -> i32) {}
+    pub fn nth(&self, memory: &Memory, n: i32) -> Result<Pointer<T>, SimError> {
+        if self.count.get() as u32 <= n as u32 { return Ok(Pointer::new(0u64)); }
+        let mut node = self.start_end.next.get();
+        for _ in 0..n { node = node.view(memory)?.next.get(); }
+        Ok(self.list_node_to_obj_with_null_check(node))
+    }
+
+    // Merge sort list, trusting count over the actual node chain as the game does
+    pub fn sort(
+        self: Pointer<Self>, memory: &mut Memory,
+        cmp: impl Fn(&Memory, Pointer<T>, Pointer<T>) -> Result<i32, SimError>
+    ) -> Result<(), SimError> {
+        let list = self.view(memory)?;
+        let count = list.count.get();
+        if count < 2 { return Ok(()); }
+
+        let (front, back) = (list.start_end.next.get(), list.start_end.prev.get());
+        self.merge_sort_impl(memory, front, back, count, &cmp)?;
+        Ok(())
+    }
+
+    fn merge_sort_impl(
+        self: Pointer<Self>, memory: &mut Memory, front: Pointer<ListNode>,
+        back: Pointer<ListNode>, num: i32,
+        cmp: &impl Fn(&Memory, Pointer<T>, Pointer<T>) -> Result<i32, SimError>
+    ) -> Result<(), SimError> {
+        if num < 2 || front == back { return Ok(()); }
+        let list = self.view(memory)?;
+
+        // Swap pair
+        if num == 2 {
+            if cmp(memory, list.list_node_to_obj(front), list.list_node_to_obj(back))? > 0 {
+                back.erase(memory)?;
+                front.insert_front(memory, back)?;
+            }
+            return Ok(());
+        }
+
+        // Split into halves
+        let num_a = num / 2;
+        let num_b = num - num_a;
+        let mut back_a = front;
+        for _ in 0..num_a - 1 { back_a = back_a.view(memory)?.next.get(); }
+        let front_b = back_a.view(memory)?.next.get();
+        let before_front = front.view(memory)?.prev.get();
+
+        // Sort halves in place
+        self.merge_sort_impl(memory, front, back_a, num_a, cmp)?;
+        self.merge_sort_impl(memory, front_b, back, num_b, cmp)?;
+
+        // Find new fronts of halves
+        let mut node_a = before_front.view(memory)?.next.get();
+        let mut node_b = node_a;
+        for _ in 0..num_a { node_b = node_b.view(memory)?.next.get(); }
+
+        // Merge halves by moving nodes of b in front of nodes of a
+        let (mut left_a, mut left_b) = (num_a, num_b);
+        while left_a > 0 && left_b > 0 {
+            let list = self.view(memory)?;
+            if cmp(memory, list.list_node_to_obj(node_a), list.list_node_to_obj(node_b))? > 0 {
+                let next_b = node_b.view(memory)?.next.get();
+                node_b.erase(memory)?;
+                node_a.insert_front(memory, node_b)?;
+                node_b = next_b;
+                left_b -= 1;
+            } else {
+                node_a = node_a.view(memory)?.next.get();
+                left_a -= 1;
+            }
+        }
+        Ok(())
+    }
 }
 
-impl<T> Updatable for OffsetList<T> {}
+impl<T> Symbolize for OffsetList<T> {
+    fn fields() -> Vec<FieldSymbol> { vec![
+        FieldSymbol::new("start_end", field!(Pointer::<Self>::NULLPTR, start_end)),
+        FieldSymbol::new("count", field!(Pointer::<Self>::NULLPTR, count)),
+        FieldSymbol::new("offset", field!(Pointer::<Self>::NULLPTR, offset)),
+    ] }
+}
+
+impl<T> Constructor for OffsetList<T> {
+    fn ctor(self: Pointer<Self>, memory: &mut Memory) -> Result<(), SimError> {
+        let start_end = field!(self, start_end);
+        field!(start_end, prev).set(memory, start_end)?;
+        field!(start_end, next).set(memory, start_end)?;
+        field!(self, count).set(memory, i32::default())?;
+        field!(self, offset).set(memory, i32::default())?;
+        Ok(())
+    }
+}
 
 #[derive(Clone, Copy)]
 #[repr(C)]
@@ -546,7 +776,14 @@ impl<T: Copy + Default, const N: i32> Default for SafeArray<T, N> where [(); N a
     fn default() -> Self { Self { buffer: [Default::default(); N as usize] } }
 }
 
-impl<T, const N: i32> Updatable for SafeArray<T, N> where [(); N as usize]: {}
+// Index straight into the buffer, so paths read like list_heads[3]
+impl<T: Symbolize, const N: i32> Symbolize for SafeArray<T, N> where [(); N as usize]: {
+    fn symbolize(path: &mut String, offset: usize) -> (usize, usize) {
+        <[T; N as usize]>::symbolize(path, offset)
+    }
+
+    fn resolve(path: &str) -> Option<(usize, usize)> { <[T; N as usize]>::resolve(path) }
+}
 
 #[derive(Clone, Copy)]
 #[repr(C)]
@@ -556,41 +793,144 @@ pub struct Lists {
     pub buffer: SafeArray<PouchItem, NUM_POUCH_ITEMS_MAX>,
 }
 
-impl Updatable for Lists {}
+impl Constructor for Lists {
+    fn ctor(self: Pointer<Self>, memory: &mut Memory) -> Result<(), SimError> {
+        let list1 = field!(self, list1);
+        let list2 = field!(self, list2);
 
-#[derive(Clone, Copy)]
+        list1.ctor(memory)?;
+        list2.ctor(memory)?;
+        for list in [list1, list2] {
+            field!(list, offset).set(
+                memory, mem::offset_of!(PouchItem, list_node) as i32
+            )?;
+        }
+
+        // Construct items and add them all to list2
+        for i in 0..NUM_POUCH_ITEMS_MAX as usize {
+            let item_ptr = field!(self, buffer.buffer[i]);
+            item_ptr.ctor(memory)?;
+            list2.push_back(memory, item_ptr)?;
+        }
+        Ok(())
+    }
+}
+
+symbolize_fields!(Lists { list1, list2, buffer });
+
+#[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct GrabbedItemInfo {
-    pub item: Pointer<PouchItem>,
-    _8: bool,
-    _9: bool,
+    pub item: Guest<Pointer<PouchItem>>,
+    _8: Guest<bool>,
+    _9: Guest<bool>,
 }
 
-impl Updatable for GrabbedItemInfo {}
+symbolize_fields!(GrabbedItemInfo { item, _8, _9 });
 
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct TypedBitFlag<Enum> {
-    bits: Enum,
+    pub bits: Guest<u32>,
+    phantom: PhantomData<Enum>,
 }
 
-impl<Enum> Updatable for TypedBitFlag<Enum> {}
-
 #[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct WeaponModifierInfo {
-    flags: TypedBitFlag<WeaponModifier>,
-    value: i32,
+    pub flags: TypedBitFlag<WeaponModifier>,
+    pub value: Guest<i32>,
 }
 
-impl Updatable for WeaponModifierInfo {}
-
 #[derive(Clone)]
 pub struct GameDataItem {
-    name: String,
-    equipped: bool,
-    value: i32,
-    data: Data,
+    pub name: String,
+    // Resolved from actor info by the game rather than saved
+    pub item_type: PouchItemType,
+    pub equipped: bool,
+    pub value: i32,
+    pub data: Data,
 }
 
 pub type GameData = Vec<GameDataItem>;
+
+#[cfg(test)]
+mod tests {
+    use crate::pmdm::*;
+    use crate::pmdm::tests::*;
+
+    use super::*;
+
+    // Values of the items list1 links in order, checking prev links mirror next links
+    fn list1_values(memory: &Memory, this: Pointer<PauseMenuDataMgr>) -> Vec<i32> {
+        let list1 = this.list1().view(memory).unwrap();
+        let start_end = field!(this.list1(), start_end);
+        let (mut prev, mut node) = (start_end, list1.start_end.next.get());
+        let mut values = vec![];
+        while node != start_end && values.len() < NUM_POUCH_ITEMS_MAX as usize {
+            let item = list1.list_node_to_obj(node).view(memory).unwrap();
+            assert!(item.list_node.prev.get() == prev, "prev link of item {}", values.len());
+            values.push(item.value.get());
+            (prev, node) = (node, item.list_node.next.get());
+        }
+        assert!(list1.start_end.prev.get() == prev, "prev link of start_end");
+        values
+    }
+
+    // Sort list1 by value with its count set to num, returning the values it links afterwards.
+    // The start_end sentinel sorts as 0 when count leads the sort into it.
+    fn sort_values(values: &[i32], num: i32) -> Vec<i32> {
+        let (mut memory, this) = create_pmdm(values.iter().map(
+            |&value| item("Item_Fruit_A", PouchItemType::Material, value)
+        ).collect());
+        field!(this.list1(), count).set(&mut memory, num).unwrap();
+        let value = |memory: &Memory, item_ptr: Pointer<PouchItem>| {
+            this.item_index(item_ptr).map_or(0, |_| item_ptr.view(memory).unwrap().value.get())
+        };
+        this.list1().sort(&mut memory, |memory, lhs, rhs| {
+            Ok(value(memory, lhs).cmp(&value(memory, rhs)) as i32)
+        }).unwrap();
+        list1_values(&memory, this)
+    }
+
+    #[test]
+    fn sort_orders_list() {
+        assert_eq!(sort_values(&[3, 1, 4, 1, 5, 9, 2, 6], 8), [1, 1, 2, 3, 4, 5, 6, 9]);
+        assert_eq!(sort_values(&[2, 1], 2), [1, 2]);
+        assert_eq!(sort_values(&[2, 1], 1), [2, 1]);
+    }
+
+    #[test]
+    fn sort_with_short_count_splits_at_count() {
+        // The sort still runs from the first to the last linked node, so with a count of 2 the
+        // first and last items are compared, and with 3 the last item joins the second half
+        assert_eq!(sort_values(&[5, 4, 3, 2, 1], 2), [1, 5, 4, 3, 2]);
+        assert_eq!(sort_values(&[5, 4, 3, 2, 1], 3), [1, 4, 5, 3, 2]);
+        assert_eq!(sort_values(&[5, 4, 3, 2, 1], 4), [1, 3, 4, 5, 2]);
+    }
+
+    #[test]
+    fn sort_with_long_count_sorts_start_end() {
+        // Counting past the last item wraps through start_end, which moves like an item
+        assert_eq!(sort_values(&[5, 4, 3, 2, 1], 6), [2, 3, 4, 5, 1]);
+        assert_eq!(sort_values(&[5, 4, 3, 2, 1], 7), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn list_counts_wrap() {
+        let (mut memory, this) = create_pmdm(vec![
+            item("Item_Fruit_A", PouchItemType::Material, 5),
+        ]);
+        let (list1, list2) = (this.list1(), this.list2());
+        let count = field!(list1, count);
+        count.set(&mut memory, i32::MAX).unwrap();
+
+        let item_ptr = list2.pop_front(&mut memory).unwrap();
+        list1.push_back(&mut memory, item_ptr).unwrap();
+        assert_eq!(count.get(&memory).unwrap(), i32::MIN);
+        list1.erase(&mut memory, item_ptr).unwrap();
+        assert_eq!(count.get(&memory).unwrap(), i32::MAX);
+        list1.push_front(&mut memory, item_ptr).unwrap();
+        assert_eq!(count.get(&memory).unwrap(), i32::MIN);
+    }
+}
@@ -0,0 +1,154 @@
+use std::mem;
+
+use crate::error::*;
+use crate::mem::*;
+use crate::platform::*;
+use crate::pmdm::*;
+
+// Vtable addresses a dump can be checked against. Switch modules are loaded at random page
+// aligned bases, so only the offset within the page is compared there.
+#[derive(Clone, Copy, Debug)]
+pub struct VTables {
+    pub pmdm: Option<u64>,
+    pub fixed_safe_string: Option<u64>,
+}
+
+impl VTables {
+    const UNKNOWN: Self = Self { pmdm: None, fixed_safe_string: None };
+}
+
+// Addresses and sizes that differ between game builds
+#[derive(Clone, Copy, Debug)]
+pub struct GameVersion {
+    pub name: &'static str,
+    pub platform: Platform,
+    // Offset of PMDM from the heap base, where recorded
+    pub pmdm_heap_offset: Option<u64>,
+    pub pmdm_size: usize,
+    pub vtables: VTables,
+}
+
+// Size of PMDM on each platform. The Switch size is that of the console dumps the simulator
+// was first written against. The Wii U size is that of the simulated layout and has not been
+// checked against a real dump yet. Either way it must match the layout built for the platform.
+const SWITCH_PMDM_SIZE: usize = 0x44808;
+const WIIU_PMDM_SIZE: usize = 0x38144;
+
+#[cfg(not(feature = "wiiu"))]
+const _: () = assert!(SWITCH_PMDM_SIZE == mem::size_of::<PauseMenuDataMgr>());
+#[cfg(feature = "wiiu")]
+const _: () = assert!(WIIU_PMDM_SIZE == mem::size_of::<PauseMenuDataMgr>());
+
+// Known versions. Vtables are not recorded yet, so only one version per platform and size is
+// listed, as dumps of versions of the same size couldn't be told apart.
+pub static GAME_VERSIONS: [GameVersion; 2] = [
+    GameVersion {
+        name: "Switch 1.6.0",
+        platform: Platform::Switch,
+        pmdm_heap_offset: Some(0xa982c8b0),
+        pmdm_size: SWITCH_PMDM_SIZE,
+        vtables: VTables::UNKNOWN,
+    },
+    GameVersion {
+        name: "Wii U 1.5.0",
+        platform: Platform::WiiU,
+        pmdm_heap_offset: None,
+        pmdm_size: WIIU_PMDM_SIZE,
+        vtables: VTables::UNKNOWN,
+    },
+];
+
+impl GameVersion {
+    // Look version up by name, ignoring case
+    pub fn find(name: &str) -> Result<&'static Self, SimError> {
+        GAME_VERSIONS.iter().find(|version| version.name.eq_ignore_ascii_case(name)).ok_or(
+            SimError::UnknownGameVersion { name: name.to_string() }
+        )
+    }
+
+    // Default version for this build when nothing else decides
+    pub fn default_for_platform() -> &'static Self {
+        GAME_VERSIONS.iter().find(|version| version.platform == PLATFORM).unwrap()
+    }
+
+    // Check that this version can be simulated by this build
+    pub fn check_platform(&self) -> Result<(), SimError> {
+        if self.platform != PLATFORM {
+            return Err(SimError::WrongPlatform { version: self.name, platform: self.platform });
+        }
+        Ok(())
+    }
+
+    // Check that a dump of this version can be simulated by this build
+    pub fn check_dump(&self, data: &[u8]) -> Result<(), SimError> {
+        self.check_platform()?;
+        if data.len() != self.pmdm_size {
+            return Err(SimError::DumpSizeMismatch {
+                version: self.name, expected: self.pmdm_size, found: data.len()
+            });
+        }
+        Ok(())
+    }
+
+    // Pick the version a PMDM dump most likely came from
+    pub fn detect(data: &[u8]) -> Result<&'static Self, SimError> {
+        let sized: Vec<_> = GAME_VERSIONS.iter().filter(
+            |version| version.pmdm_size == data.len()
+        ).collect();
+        let Some(&first) = sized.first() else {
+            return Err(SimError::UnknownDumpSize { found: data.len() });
+        };
+
+        // Only versions of this platform can be simulated, so say which build the dump needs
+        let candidates: Vec<_> = sized.into_iter().filter(
+            |version| version.platform == PLATFORM
+        ).collect();
+        if candidates.is_empty() {
+            return Err(SimError::WrongPlatform { version: first.name, platform: first.platform });
+        }
+
+        let pmdm_vtable = dump_pointer(data, mem::offset_of!(PauseMenuDataMgr, vptr));
+        let name_vtable = dump_pointer(
+            data, mem::offset_of!(PauseMenuDataMgr, newly_added_item.name.vptr)
+        );
+        let matches: Vec<_> = candidates.iter().filter(|version| {
+            vtable_matches(version.vtables.pmdm, pmdm_vtable)
+                && vtable_matches(version.vtables.fixed_safe_string, name_vtable)
+        }).collect();
+        match matches[..] {
+            [&version] => Ok(version),
+            _ => Err(SimError::AmbiguousGameVersion {
+                candidates: candidates.iter().map(|version| version.name).collect()
+            }),
+        }
+    }
+}
+
+// Read guest pointer from dump data
+fn dump_pointer(data: &[u8], offset: usize) -> Pointer {
+    let bytes = &data[offset..offset + mem::size_of::<Address>()];
+    Pointer::from_guest(Address::from_ne_bytes(bytes.try_into().unwrap()))
+}
+
+fn vtable_matches(expected: Option<u64>, found: Pointer) -> bool {
+    let Some(expected) = expected else { return true; };
+    match PLATFORM {
+        Platform::Switch => expected & 0xfff == found.address & 0xfff,
+        Platform::WiiU => expected == found.address,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_picks_version_of_dump_size() {
+        let data = vec![0; mem::size_of::<PauseMenuDataMgr>()];
+        let version = GameVersion::detect(&data).unwrap();
+        assert_eq!(version.platform, PLATFORM);
+        assert!(matches!(
+            GameVersion::detect(&data[1..]), Err(SimError::UnknownDumpSize { .. })
+        ));
+    }
+}